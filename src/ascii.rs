@@ -0,0 +1,299 @@
+//! MC3E/MC4E ASCII code support.
+//!
+//! A PLC port is configured for either binary or ASCII communication data
+//! code. `MockServer::with_data_code(DataCode::Ascii)` makes the TCP listener
+//! behave like an ASCII port: ASCII frames are translated to the binary
+//! layout, answered through the normal response path and translated back,
+//! while binary frames are not answered (the connection is reset).
+//!
+//! Request:  "5000" | "5400" serial(4) "0000", network(2) pc(2) io(4)
+//!           station(2) data_len(4) monitoring_timer(4) command(4) sub(4) ...
+//! Response: "D000" | "D400" serial(4) "0000", route as requested,
+//!           data_len(4) end_code(4) [data | error information]
+//!
+//! Numbers are upper-case hex text, most significant digit first; `data_len`
+//! counts the characters following it. Only batch read/write (0x0401/0x1401)
+//! in word (0x0000) and bit (0x0001) units are translated: device code(2,
+//! e.g. "D*"), device number(6, hex for X/Y/B/W/SB/SW/DX/DY, else decimal),
+//! points(4), then 4 characters per word or 1 character ('0'/'1') per bit.
+//! Other commands are answered with end-code 0xC059, unknown devices and
+//! malformed device numbers with `END_CODE_DEVICE`.
+
+use anyhow::Context;
+
+use crate::handler::END_CODE_COMMAND_NOT_SUPPORTED;
+
+/// Communication data code of the mock's MC3E/MC4E TCP port.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DataCode {
+    #[default]
+    Binary,
+    Ascii,
+}
+
+/// End-code for a device code or device number the mock cannot resolve.
+pub const END_CODE_DEVICE: u16 = 0xC05B;
+
+/// Length of the header up to and including `data_len`.
+const MC3E_HEADER_LEN: usize = 18;
+const MC4E_HEADER_LEN: usize = 26;
+
+/// Devices whose numbers are written in hex.
+const HEX_NUMBERED: [&str; 8] = ["X", "Y", "B", "W", "SB", "SW", "DX", "DY"];
+
+/// Total length of the ASCII request at the start of `buf`.
+///
+/// Returns `Ok(None)` while the header is incomplete and an error when `buf`
+/// does not start with an ASCII request subheader (e.g. a binary frame).
+pub fn frame_len(buf: &[u8]) -> anyhow::Result<Option<usize>> {
+    let Some(sub) = buf.get(..4) else {
+        if !b"5000".starts_with(buf) && !b"5400".starts_with(buf) {
+            anyhow::bail!("not an ASCII request subheader");
+        }
+        return Ok(None);
+    };
+    let header_len = match sub {
+        b"5000" => MC3E_HEADER_LEN,
+        b"5400" => MC4E_HEADER_LEN,
+        _ => anyhow::bail!("not an ASCII request subheader: {:02X?}", sub),
+    };
+    if buf.len() < header_len {
+        return Ok(None);
+    }
+    let data_len = hex_field(&buf[header_len - 4..header_len])?;
+    Ok(Some(header_len + usize::from(data_len)))
+}
+
+/// An ASCII request translated to a binary MC3E/MC4E frame.
+#[derive(Debug)]
+pub struct Translated {
+    /// Complete binary request frame.
+    pub frame: Vec<u8>,
+    pub command: u16,
+    pub sub: u16,
+    /// Points requested by a batch read/write.
+    pub points: usize,
+    /// Response prefix up to the route: "D000" or "D400" serial "0000" route.
+    head: Vec<u8>,
+}
+
+/// Translate one complete ASCII request (as delimited by `frame_len`).
+/// Commands without an ASCII translation (0xC059) and unknown devices or
+/// malformed device numbers (`END_CODE_DEVICE`) are reported as `Err`
+/// carrying the ready-to-send error response.
+pub fn to_binary(frame: &[u8]) -> anyhow::Result<Result<Translated, Vec<u8>>> {
+    let text = std::str::from_utf8(frame).context("ASCII frame is not text")?;
+    let mc4e = text.starts_with("5400");
+    let route_at = if mc4e { 12 } else { 4 };
+    let serial = if mc4e { hex_field(&frame[4..8])? } else { 0 };
+    let route = &frame[route_at..route_at + 10];
+    let mut pos = route_at + 14; // route(10) data_len(4)
+    let mut next = |len: usize| -> anyhow::Result<&[u8]> {
+        let field = frame
+            .get(pos..pos + len)
+            .with_context(|| format!("ASCII frame truncated at {}", pos))?;
+        pos += len;
+        Ok(field)
+    };
+    let timer = hex_field(next(4)?)?;
+    let command = hex_field(next(4)?)?;
+    let sub = hex_field(next(4)?)?;
+
+    let mut head: Vec<u8> = Vec::new();
+    if mc4e {
+        head.extend_from_slice(b"D400");
+        head.extend_from_slice(&frame[4..8]);
+        head.extend_from_slice(b"0000");
+    } else {
+        head.extend_from_slice(b"D000");
+    }
+    head.extend_from_slice(route);
+
+    // error response carrying the route, command and subcommand as information
+    let reject = |end_code: u16| {
+        let mut info = route.to_vec();
+        info.extend_from_slice(format!("{:04X}{:04X}", command, sub).as_bytes());
+        response(&head, end_code, &info)
+    };
+
+    if !matches!(command, 0x0401 | 0x1401) || !matches!(sub, 0x0000 | 0x0001) {
+        tracing::warn!(
+            command = %format!("0x{:04X}", command),
+            sub = %format!("0x{:04X}", sub),
+            "command has no ASCII translation"
+        );
+        return Ok(Err(reject(END_CODE_COMMAND_NOT_SUPPORTED)));
+    }
+
+    let symbol: String = String::from_utf8_lossy(next(2)?)
+        .trim_end_matches(['*', ' '])
+        .to_string();
+    let Some(dev) = melsec_mc::device::device_by_symbol(&symbol) else {
+        tracing::warn!(symbol = %symbol, "unknown ASCII device code");
+        return Ok(Err(reject(END_CODE_DEVICE)));
+    };
+    let number_text = String::from_utf8_lossy(next(6)?).to_string();
+    let radix = if HEX_NUMBERED.contains(&symbol.as_str()) {
+        16
+    } else {
+        10
+    };
+    let Ok(number) = u32::from_str_radix(&number_text, radix) else {
+        tracing::warn!(number = %number_text, radix, "invalid ASCII device number");
+        return Ok(Err(reject(END_CODE_DEVICE)));
+    };
+    let points = hex_field(next(4)?)?;
+
+    let mut data: Vec<u8> = Vec::new();
+    data.extend_from_slice(&command.to_le_bytes());
+    data.extend_from_slice(&sub.to_le_bytes());
+    data.extend_from_slice(&number.to_le_bytes()[..3]);
+    data.push(dev.device_code_q().to_le_bytes()[0]);
+    data.extend_from_slice(&points.to_le_bytes());
+    if command == 0x1401 {
+        if sub == 0x0001 {
+            let bits = next(usize::from(points))?;
+            for pair in bits.chunks(2) {
+                let hi = u8::from(pair[0] != b'0');
+                let lo = u8::from(pair.get(1).is_some_and(|b| *b != b'0'));
+                data.push((hi << 4) | lo);
+            }
+        } else {
+            for _ in 0..points {
+                data.extend_from_slice(&hex_field(next(4)?)?.to_le_bytes());
+            }
+        }
+    }
+
+    let mut bin: Vec<u8> = Vec::new();
+    if mc4e {
+        bin.extend_from_slice(&melsec_mc::mc_define::MC_SUBHEADER_REQUEST);
+        bin.extend_from_slice(&serial.to_le_bytes());
+        bin.extend_from_slice(&0u16.to_le_bytes());
+    } else {
+        bin.extend_from_slice(&[0x50u8, 0x00u8]);
+    }
+    bin.push(u8::try_from(hex_field(&route[0..2])?)?);
+    bin.push(u8::try_from(hex_field(&route[2..4])?)?);
+    bin.extend_from_slice(&hex_field(&route[4..8])?.to_le_bytes());
+    bin.push(u8::try_from(hex_field(&route[8..10])?)?);
+    bin.extend_from_slice(&u16::try_from(data.len() + 2)?.to_le_bytes());
+    bin.extend_from_slice(&timer.to_le_bytes());
+    bin.extend_from_slice(&data);
+
+    Ok(Ok(Translated {
+        frame: bin,
+        command,
+        sub,
+        points: usize::from(points),
+        head,
+    }))
+}
+
+/// Translate the binary response frame for `req` back to ASCII.
+pub fn to_ascii(req: &Translated, resp: &[u8]) -> Vec<u8> {
+    let off = if resp.get(..2) == Some(&melsec_mc::mc_define::MC_SUBHEADER_RESPONSE[..]) {
+        13
+    } else {
+        9
+    };
+    let Some(end_code) = resp
+        .get(off..off + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    else {
+        return response(&req.head, 0x0050, &[]);
+    };
+    let data = &resp[off + 2..];
+    let mut out: Vec<u8> = Vec::new();
+    if end_code != 0x0000 {
+        // error information: route(5) command(2le) subcommand(2le)
+        if data.len() >= 9 {
+            out.extend_from_slice(
+                format!(
+                    "{:02X}{:02X}{:04X}{:02X}{:04X}{:04X}",
+                    data[0],
+                    data[1],
+                    u16::from_le_bytes([data[2], data[3]]),
+                    data[4],
+                    u16::from_le_bytes([data[5], data[6]]),
+                    u16::from_le_bytes([data[7], data[8]])
+                )
+                .as_bytes(),
+            );
+        }
+    } else if req.command == 0x0401 && req.sub == 0x0001 {
+        for i in 0..req.points {
+            let b = data.get(i / 2).copied().unwrap_or(0);
+            let v = if i % 2 == 0 { b >> 4 } else { b & 0x0F };
+            out.push(if v != 0 { b'1' } else { b'0' });
+        }
+    } else {
+        for w in data.chunks_exact(2) {
+            out.extend_from_slice(format!("{:04X}", u16::from_le_bytes([w[0], w[1]])).as_bytes());
+        }
+    }
+    response(&req.head, end_code, &out)
+}
+
+fn response(head: &[u8], end_code: u16, data: &[u8]) -> Vec<u8> {
+    let mut out = head.to_vec();
+    out.extend_from_slice(format!("{:04X}{:04X}", data.len() + 4, end_code).as_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+fn hex_field(field: &[u8]) -> anyhow::Result<u16> {
+    let text = std::str::from_utf8(field)?;
+    u16::from_str_radix(text, 16).with_context(|| format!("invalid hex field {:?}", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_len_reads_ascii_data_len() {
+        let frame = b"500000FF03FF000018001004010000D*0001000003";
+        assert_eq!(frame_len(&frame[..10]).unwrap(), None);
+        assert_eq!(frame_len(frame).unwrap(), Some(frame.len()));
+        assert_eq!(frame_len(b"50").unwrap(), None);
+        assert!(frame_len(&[0x50, 0x00, 0x00, 0xFF]).is_err());
+    }
+
+    #[test]
+    fn word_read_round_trips_through_binary() {
+        let frame = b"500000FF03FF000018001004010000D*0001000002";
+        let req = to_binary(frame).unwrap().unwrap();
+        assert_eq!(
+            req.frame[7..],
+            [0x0C, 0x00, 0x10, 0x00, 0x01, 0x04, 0x00, 0x00, 0x64, 0x00, 0x00, 0xA8, 0x02, 0x00]
+        );
+        let resp = [
+            0xD0, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x06, 0x00, 0x00, 0x00, 0x34, 0x12, 0xCD,
+            0xAB,
+        ];
+        assert_eq!(
+            to_ascii(&req, &resp),
+            b"D00000FF03FF00000C00001234ABCD".to_vec()
+        );
+    }
+
+    #[test]
+    fn unsupported_command_gets_c059() {
+        let frame = b"500000FF03FF00000E001006190000AB";
+        let resp = to_binary(frame).unwrap().unwrap_err();
+        assert_eq!(resp, b"D00000FF03FF000016C05900FF03FF0006190000".to_vec());
+    }
+
+    #[test]
+    fn unknown_device_gets_an_error_response() {
+        let frame = b"500000FF03FF000018001004010000??0001000001";
+        let resp = to_binary(frame).unwrap().unwrap_err();
+        assert_eq!(resp, b"D00000FF03FF000016C05B00FF03FF0004010000".to_vec());
+
+        // "00010Z" is not a decimal D number
+        let frame = b"500000FF03FF000018001004010000D*00010Z0001";
+        let resp = to_binary(frame).unwrap().unwrap_err();
+        assert_eq!(resp, b"D00000FF03FF000016C05B00FF03FF0004010000".to_vec());
+    }
+}
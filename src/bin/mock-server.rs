@@ -10,6 +10,15 @@ struct Opts {
     /// optional UDP listen address, e.g. 127.0.0.1:5001
     #[clap(long)]
     udp: Option<String>,
+    /// optional MC1E (1E frame) TCP listen address, e.g. 127.0.0.1:5002
+    #[clap(long)]
+    mc1e: Option<String>,
+    /// serve the --listen port in ASCII communication data code instead of binary
+    #[clap(long)]
+    ascii: bool,
+    /// serve the mock-only masked word write (0x1402/0x0010) instead of answering 0xC059
+    #[clap(long)]
+    masked_word_write: bool,
     /// TIM_AWAIT timeout in milliseconds (overrides MELSEC_MOCK_TIM_AWAIT_MS env var)
     #[clap(long)]
     tim_await_ms: Option<u64>,
@@ -23,7 +32,8 @@ async fn main() -> anyhow::Result<()> {
     let opts = Opts::parse();
     tracing_subscriber::fmt::init();
 
-    let server = melsec_mc_mock::MockServer::new_with_assignment(opts.device_assignment.as_deref());
+    let server = melsec_mc_mock::MockServer::new_with_assignment(opts.device_assignment.as_deref())
+        .with_masked_word_write(opts.masked_word_write);
     // If tim_await_ms provided via CLI, set environment variable so server picks it up
     if let Some(ms) = opts.tim_await_ms {
         std::env::set_var("MELSEC_MOCK_TIM_AWAIT_MS", ms.to_string());
@@ -43,7 +53,26 @@ async fn main() -> anyhow::Result<()> {
         });
     }
 
+    // If an MC1E address is provided, serve 1E frames on their own listener
+    if let Some(mc1e_bind) = opts.mc1e.clone() {
+        let mc1e_srv = server.clone();
+        tracing::info!(mc1e = %mc1e_bind, "starting MC1E listener");
+        tokio::spawn(async move {
+            if let Err(e) = mc1e_srv.run_mc1e_listener(&mc1e_bind).await {
+                tracing::error!(%e, "mc1e listener failed");
+            }
+        });
+    }
+
     // Run the MC listener (blocks until error)
-    server.run_listener(&opts.listen).await?;
+    let data_code = if opts.ascii {
+        melsec_mc_mock::ascii::DataCode::Ascii
+    } else {
+        melsec_mc_mock::ascii::DataCode::Binary
+    };
+    server
+        .with_data_code(data_code)
+        .run_listener(&opts.listen)
+        .await?;
     Ok(())
 }
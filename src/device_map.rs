@@ -1,6 +1,6 @@
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
@@ -100,6 +100,45 @@ impl<'de> serde::Deserialize<'de> for DeviceKey {
 }
 pub type Word = u16;
 
+/// Word addresses at or above this offset are kept in a sparse map instead of
+/// the dense per-device vector, so 4-byte addresses (e.g. ZR beyond 0xFFFFFF)
+/// don't force a multi-gigabyte allocation.
+pub const DENSE_ADDR_LIMIT: usize = 0x10_0000;
+
+/// Value returned for device words that were never written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadDefault {
+    /// Unwritten words read as 0 (like a freshly cleared PLC).
+    #[default]
+    Zero,
+    /// Unwritten words read as the given pattern (e.g. 0xAAAA).
+    Fill(Word),
+    /// Unwritten words read as the low word of their own address.
+    AddressEcho,
+}
+
+impl ReadDefault {
+    pub fn word_at(self, addr: usize) -> Word {
+        match self {
+            ReadDefault::Zero => 0,
+            ReadDefault::Fill(w) => w,
+            ReadDefault::AddressEcho => (addr & 0xFFFF) as Word,
+        }
+    }
+}
+
+/// One entry of the PLC error history served by command 0x0626.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorLogEntry {
+    /// Error code (as reported in SD0).
+    pub code: u16,
+    /// Occurrence time as BCD bytes: year (last two digits), month, day,
+    /// hour, minute, second.
+    pub timestamp: [u8; 6],
+    /// Detailed error information (first word).
+    pub detail: u16,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 /// In-memory storage for mock PLC device areas.
 ///
@@ -108,12 +147,34 @@ pub type Word = u16;
 /// 実機の永続スナップショット読み書きや TOML による初期化をサポートします。
 pub struct DeviceMap {
     inner: HashMap<DeviceKey, Vec<Word>>,
+    /// Intelligent function module buffer memory keyed by the module's start
+    /// I/O number (e.g. 0x0040). Missing in older snapshots, hence the default.
+    #[serde(default)]
+    modules: HashMap<u16, Vec<Word>>,
+    /// Words at addresses >= `DENSE_ADDR_LIMIT`, keyed by device then address.
+    #[serde(default)]
+    sparse: HashMap<DeviceKey, BTreeMap<usize, Word>>,
+    /// Error history, oldest first.
+    #[serde(default)]
+    error_log: Vec<ErrorLogEntry>,
+    /// Bitset (one bit per dense word) of addresses written through
+    /// `set_words`; preallocated areas from `populate_from_toml` are not
+    /// marked. Only consulted when `read_default` is not `Zero`.
+    #[serde(default)]
+    written: HashMap<DeviceKey, Vec<u64>>,
+    #[serde(skip)]
+    read_default: ReadDefault,
 }
 
 impl DeviceMap {
     pub fn new() -> Self {
         Self {
             inner: HashMap::new(),
+            modules: HashMap::new(),
+            sparse: HashMap::new(),
+            error_log: Vec::new(),
+            written: HashMap::new(),
+            read_default: ReadDefault::Zero,
         }
     }
 
@@ -124,6 +185,19 @@ impl DeviceMap {
     /// let w = dm.get_words("D", 0, 1);
     /// ```
     pub fn set_words(&mut self, key: &str, addr: usize, words: &[Word]) {
+        self.store_words(key, addr, words, true);
+    }
+
+    /// Select what `get_words` returns for words never written.
+    pub fn set_read_default(&mut self, read_default: ReadDefault) {
+        self.read_default = read_default;
+    }
+
+    pub fn read_default(&self) -> ReadDefault {
+        self.read_default
+    }
+
+    fn store_words(&mut self, key: &str, addr: usize, words: &[Word], mark_written: bool) {
         // Centralize key/address normalization using melsec_mc helpers.
         let (dkey, resolved_addr) = normalize_key_addr(key, addr);
         tracing::debug!(key = %key, resolved_key = ?dkey, addr = resolved_addr, words = ?words, "device_map.set_words called");
@@ -135,11 +209,34 @@ impl DeviceMap {
         };
         let dk = DeviceKey::from_code(code_val);
         tracing::debug!(orig_key = %key, code = code_val, resolved_addr = resolved_addr, words = ?words, "device_map.set_words resolved");
-        let vec = self.inner.entry(dk).or_default();
-        if vec.len() < resolved_addr + words.len() {
-            vec.resize(resolved_addr + words.len(), 0);
+        // words below DENSE_ADDR_LIMIT go to the dense vector, the rest to the sparse map
+        let split = DENSE_ADDR_LIMIT
+            .saturating_sub(resolved_addr)
+            .min(words.len());
+        let (dense, sparse) = words.split_at(split);
+        if !dense.is_empty() {
+            let vec = self.inner.entry(dk).or_default();
+            if vec.len() < resolved_addr + dense.len() {
+                vec.resize(resolved_addr + dense.len(), 0);
+            }
+            vec[resolved_addr..resolved_addr + dense.len()].copy_from_slice(dense);
+            if mark_written {
+                let bits = self.written.entry(dk).or_default();
+                let end = resolved_addr + dense.len();
+                if bits.len() < end.div_ceil(64) {
+                    bits.resize(end.div_ceil(64), 0);
+                }
+                for a in resolved_addr..end {
+                    bits[a / 64] |= 1 << (a % 64);
+                }
+            }
+        }
+        if !sparse.is_empty() {
+            let map = self.sparse.entry(dk).or_default();
+            for (i, w) in sparse.iter().enumerate() {
+                map.insert(resolved_addr + split + i, *w);
+            }
         }
-        vec[resolved_addr..resolved_addr + words.len()].copy_from_slice(words);
     }
 
     pub fn get_words(&self, key: &str, addr: usize, count: usize) -> Vec<Word> {
@@ -151,26 +248,80 @@ impl DeviceMap {
             dkey.parse::<u8>().unwrap_or(0u8)
         };
         let dk = DeviceKey::from_code(code_val);
-        match self.inner.get(&dk) {
-            Some(vec) => {
-                let mut out = Vec::with_capacity(count);
-                for i in 0..count {
-                    out.push(*vec.get(resolved_addr + i).unwrap_or(&0));
-                }
-                out
-            }
+        let dense = self.inner.get(&dk);
+        let sparse = self.sparse.get(&dk);
+        let mut out = Vec::with_capacity(count);
+        for i in 0..count {
+            let a = resolved_addr + i;
+            let w = if a >= DENSE_ADDR_LIMIT {
+                sparse.and_then(|m| m.get(&a))
+            } else if self.read_default == ReadDefault::Zero || self.is_written(dk, a) {
+                dense.and_then(|v| v.get(a))
+            } else {
+                None
+            };
+            out.push(w.copied().unwrap_or_else(|| self.read_default.word_at(a)));
+        }
+        out
+    }
+
+    fn is_written(&self, dk: DeviceKey, addr: usize) -> bool {
+        self.written
+            .get(&dk)
+            .and_then(|bits| bits.get(addr / 64))
+            .is_some_and(|b| b & (1 << (addr % 64)) != 0)
+    }
+
+    /// Write words into the buffer memory of the intelligent function module
+    /// at start I/O number `module_io`. `addr` is the buffer memory (`Un\G`) word address.
+    pub fn set_module_words(&mut self, module_io: u16, addr: usize, words: &[Word]) {
+        tracing::debug!(module_io, addr, words = ?words, "device_map.set_module_words called");
+        let vec = self.modules.entry(module_io).or_default();
+        if vec.len() < addr + words.len() {
+            vec.resize(addr + words.len(), 0);
+        }
+        vec[addr..addr + words.len()].copy_from_slice(words);
+    }
+
+    /// Read words from intelligent function module buffer memory. Unwritten
+    /// addresses read as zero.
+    pub fn get_module_words(&self, module_io: u16, addr: usize, count: usize) -> Vec<Word> {
+        match self.modules.get(&module_io) {
+            Some(vec) => (0..count)
+                .map(|i| *vec.get(addr + i).unwrap_or(&0))
+                .collect(),
             None => vec![0; count],
         }
     }
 
+    /// Append an entry to the error history.
+    pub fn push_error_log(&mut self, entry: ErrorLogEntry) {
+        self.error_log.push(entry);
+    }
+
+    /// Error history entries starting at `start` (0 = oldest). Fewer than
+    /// `count` entries are returned when the history is shorter.
+    pub fn error_log(&self, start: usize, count: usize) -> &[ErrorLogEntry] {
+        let start = start.min(self.error_log.len());
+        let end = start.saturating_add(count).min(self.error_log.len());
+        &self.error_log[start..end]
+    }
+
     /// Clear all stored device words (management helper)
     pub fn clear(&mut self) {
         self.inner.clear();
+        self.modules.clear();
+        self.sparse.clear();
+        self.error_log.clear();
+        self.written.clear();
     }
 
     /// Return true when the internal map is empty
     pub fn is_empty(&self) -> bool {
         self.inner.is_empty()
+            && self.modules.is_empty()
+            && self.sparse.is_empty()
+            && self.error_log.is_empty()
     }
 
     /// Return whether the given key is present in the internal map (used for tests).
@@ -182,7 +333,7 @@ impl DeviceMap {
             dkey.parse::<u8>().unwrap_or(0u8)
         };
         let dk = DeviceKey::from_code(code_val);
-        self.inner.contains_key(&dk)
+        self.inner.contains_key(&dk) || self.sparse.contains_key(&dk)
     }
 
     /// Save the current device map to a JSON file at the given path.
@@ -203,10 +354,24 @@ impl DeviceMap {
         }
         let f = File::open(p)?;
         let r = BufReader::new(f);
-        let dm: DeviceMap = serde_json::from_reader(r)?;
+        let v: serde_json::Value = serde_json::from_reader(r)?;
+        // snapshots saved before `written` existed: every stored word was
+        // written by a client, so it must stay visible under a read default
+        let legacy = v.get("written").is_none();
+        let mut dm: DeviceMap = serde_json::from_value(v)?;
+        if legacy {
+            dm.mark_all_written();
+        }
         Ok(Some(dm))
     }
 
+    fn mark_all_written(&mut self) {
+        for (dk, words) in &self.inner {
+            self.written
+                .insert(*dk, vec![u64::MAX; words.len().div_ceil(64)]);
+        }
+    }
+
     /// Populate the device map from a TOML file with the following simple format:
     ///
     /// ```toml
@@ -286,7 +451,7 @@ impl DeviceMap {
                     // initialize each target with zeroed words
                     let zeros = vec![0u16; count];
                     for t in targets {
-                        self.set_words(t, 0, &zeros);
+                        self.store_words(t, 0, &zeros, false);
                         tracing::info!(symbol=%t, points=count, "populated device points from toml (expanded)");
                     }
                 } else {
@@ -349,4 +514,72 @@ mod tests {
         assert!(dm.has_key("ZR"), "set_words should create ZR entry");
         assert_eq!(dm.get_words("ZR", 0, 1), vec![0x1234u16]);
     }
+
+    #[test]
+    fn large_addresses_are_stored_sparsely() {
+        let mut dm = DeviceMap::new();
+        // beyond the 3-byte address range and near the 4-byte limit
+        dm.set_words("ZR", 0x0100_0000, &[0x1111u16, 0x2222u16]);
+        dm.set_words("ZR", 0xFFFF_FFF0, &[0x3333u16]);
+        assert_eq!(
+            dm.get_words("ZR", 0x0100_0000, 3),
+            vec![0x1111u16, 0x2222u16, 0u16]
+        );
+        assert_eq!(dm.get_words("ZR", 0xFFFF_FFF0, 1), vec![0x3333u16]);
+        // the dense vector was never grown for these writes
+        assert!(!dm.inner.contains_key(&DeviceKey::from_code(0xB0)));
+
+        // a write straddling the limit is split between dense and sparse storage
+        dm.set_words("D", DENSE_ADDR_LIMIT - 1, &[0xAAAAu16, 0xBBBBu16]);
+        assert_eq!(
+            dm.get_words("D", DENSE_ADDR_LIMIT - 1, 2),
+            vec![0xAAAAu16, 0xBBBBu16]
+        );
+        assert_eq!(
+            dm.inner.get(&DeviceKey::from_code(0xA8)).map(Vec::len),
+            Some(DENSE_ADDR_LIMIT)
+        );
+    }
+
+    #[test]
+    fn read_default_applies_to_unwritten_words_only() {
+        let mut dm = DeviceMap::new();
+        // preallocated (populated) words do not count as written
+        dm.store_words("D", 0, &[0u16; 16], false);
+        dm.set_words("D", 4, &[0x1234u16]);
+
+        assert_eq!(dm.get_words("D", 3, 3), vec![0, 0x1234, 0]);
+
+        dm.set_read_default(ReadDefault::Fill(0xAAAA));
+        assert_eq!(dm.get_words("D", 3, 3), vec![0xAAAA, 0x1234, 0xAAAA]);
+        assert_eq!(dm.get_words("W", 0, 1), vec![0xAAAA]);
+
+        dm.set_read_default(ReadDefault::AddressEcho);
+        assert_eq!(dm.get_words("D", 3, 3), vec![3, 0x1234, 5]);
+        assert_eq!(dm.get_words("D", 0x1_0005, 1), vec![0x0005]);
+        assert_eq!(dm.get_words("ZR", 0x0200_0010, 1), vec![0x0010]);
+
+        // a written zero stays zero under any default
+        dm.set_words("D", 5, &[0u16]);
+        assert_eq!(dm.get_words("D", 5, 1), vec![0]);
+    }
+
+    #[test]
+    fn legacy_snapshot_words_count_as_written() {
+        let mut dm = DeviceMap::new();
+        dm.set_words("D", 2, &[0x1234u16, 0x0000]);
+        let mut v = serde_json::to_value(&dm).unwrap();
+        v.as_object_mut().unwrap().remove("written");
+        let path = std::env::temp_dir().join(format!(
+            "melsec_mock_legacy_snapshot_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, serde_json::to_vec(&v).unwrap()).unwrap();
+
+        let mut loaded = DeviceMap::load_from_file(&path).unwrap().unwrap();
+        let _ = std::fs::remove_file(&path);
+        loaded.set_read_default(ReadDefault::Fill(0xAAAA));
+        assert_eq!(loaded.get_words("D", 2, 2), vec![0x1234, 0x0000]);
+        assert_eq!(loaded.get_words("D", 0x100, 1), vec![0xAAAA]);
+    }
 }
@@ -5,6 +5,44 @@ use anyhow::Result;
 
 use crate::device_map::DeviceMap;
 
+/// Maximum bytes per intelligent function module buffer memory read (0x0601).
+pub const MODULE_BUFFER_MAX_BYTES: usize = 1920;
+
+/// Per-frame point limits for batch read/write (0x0401/0x1401, binary code).
+pub const MAX_WORD_POINTS: usize = 960;
+pub const MAX_BIT_POINTS: usize = 7168;
+
+/// Command/subcommand of the masked word write. This is not an MC protocol
+/// command: no published command carries a mask, so the pair is the mock's
+/// convention, served only through `handle_masked_word_write` when a server
+/// opts in (`MockServer::with_masked_word_write`). Otherwise it is answered
+/// with `END_CODE_COMMAND_NOT_SUPPORTED` unless the command registry defines it.
+pub const MASKED_WORD_WRITE_COMMAND: u16 = 0x1402;
+pub const MASKED_WORD_WRITE_SUBCOMMAND: u16 = 0x0010;
+
+/// End-code a PLC returns when the number of requested points is out of range.
+pub const END_CODE_POINTS_OUT_OF_RANGE: u16 = 0xC051;
+
+/// End-code a PLC returns when the request data is shorter than its command
+/// layout requires.
+pub const END_CODE_REQUEST_LENGTH: u16 = 0xC061;
+
+/// End-code a PLC returns for a command/subcommand it does not support.
+pub const END_CODE_COMMAND_NOT_SUPPORTED: u16 = 0xC059;
+
+/// Handler error carrying a PLC end-code. The server answers with an error
+/// response frame using this code instead of an empty success payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndCodeError(pub u16);
+
+impl std::fmt::Display for EndCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request rejected with end-code 0x{:04X}", self.0)
+    }
+}
+
+impl std::error::Error for EndCodeError {}
+
 /// This file contains the request handling and spec-driven response builder
 /// implementations migrated from the previous monolithic `lib.rs`.
 // test helpers and unit tests are placed in the bottom `tests` module to avoid
@@ -12,6 +50,67 @@ use crate::device_map::DeviceMap;
 pub async fn handle_request_and_apply_store(
     store: &Arc<RwLock<DeviceMap>>,
     req: &melsec_mc::request::McRequest,
+) -> Result<Vec<u8>> {
+    handle_request_with_registry(store, None, req).await
+}
+
+/// Serve the mock-only masked word write. Returns `None` when `req` is not
+/// 0x1402/0x0010 or the command registry (the given one, else the global one)
+/// defines the pair, so the caller dispatches it normally.
+/// Request: command(2le) subcommand(2le) device_no:4le device_code:2le value:2le mask:2le
+/// Only the bits set in mask take their value from `value`. Response: empty payload
+pub async fn handle_masked_word_write(
+    store: &Arc<RwLock<DeviceMap>>,
+    registry: Option<&melsec_mc::command_registry::CommandRegistry>,
+    req: &melsec_mc::request::McRequest,
+) -> Option<Result<Vec<u8>>> {
+    let data = &req.request_data;
+    if data.len() < 4
+        || u16::from_le_bytes([data[0], data[1]]) != MASKED_WORD_WRITE_COMMAND
+        || u16::from_le_bytes([data[2], data[3]]) != MASKED_WORD_WRITE_SUBCOMMAND
+    {
+        return None;
+    }
+    // an explicit registry is the complete command set and decides on its own
+    if registry.is_some()
+        || melsec_mc::command_registry::CommandRegistry::global()
+            .and_then(|reg| {
+                reg.find_by_code_and_sub(
+                    MASKED_WORD_WRITE_COMMAND,
+                    MASKED_WORD_WRITE_SUBCOMMAND,
+                    None,
+                )
+            })
+            .is_some()
+    {
+        return None;
+    }
+    if data.len() < 14 {
+        tracing::warn!(len = data.len(), "masked word write request too short");
+        return Some(Err(EndCodeError(END_CODE_REQUEST_LENGTH).into()));
+    }
+    let addr = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let dev_code = u16::from_le_bytes([data[8], data[9]]);
+    let value = u16::from_le_bytes([data[10], data[11]]);
+    let mask = u16::from_le_bytes([data[12], data[13]]);
+    let key_literal = format!("0x{:02X}", u8::try_from(dev_code).unwrap_or(0u8));
+    let mut s = store.write().await;
+    let old = s.get_words(&key_literal, addr, 1)[0];
+    let new = (old & !mask) | (value & mask);
+    tracing::info!(key = %key_literal, addr, old, new, mask, "apply masked write_word to store");
+    s.set_words(&key_literal, addr, &[new]);
+    Some(Ok(Vec::new()))
+}
+
+/// Same as `handle_request_and_apply_store` but dispatches through the given
+/// `CommandRegistry` instead of the global one. An explicit registry is the
+/// complete command set: commands it does not define are rejected with
+/// `END_CODE_COMMAND_NOT_SUPPORTED`. `None` falls back to
+/// `CommandRegistry::global()` and the mock's built-in handlers.
+pub async fn handle_request_with_registry(
+    store: &Arc<RwLock<DeviceMap>>,
+    registry: Option<&melsec_mc::command_registry::CommandRegistry>,
+    req: &melsec_mc::request::McRequest,
 ) -> Result<Vec<u8>> {
     // data slice contains the request body (command/subcommand/...)
     let data = &req.request_data;
@@ -21,9 +120,20 @@ pub async fn handle_request_and_apply_store(
     let command = u16::from_le_bytes([data[0], data[1]]);
     let sub = u16::from_le_bytes([data[2], data[3]]);
 
+    if let Some(reg) = registry {
+        if reg.find_by_code_and_sub(command, sub, None).is_none() {
+            tracing::warn!(
+                command = %format!("0x{:04X}", command),
+                sub = %format!("0x{:04X}", sub),
+                "command not defined by the loaded command set"
+            );
+            return Err(EndCodeError(END_CODE_COMMAND_NOT_SUPPORTED).into());
+        }
+    }
+
     // Try to find a CommandSpec for this numeric command+subcommand. If found
     // prefer the typed CommandSpec and build a response using its response_description.
-    let registry_opt = melsec_mc::command_registry::CommandRegistry::global();
+    let registry_opt = registry.or(melsec_mc::command_registry::CommandRegistry::global());
 
     // helpers to read common fields
     // Support both legacy MC3E-style layout (start_addr:3le, device_code:1, count:2)
@@ -120,6 +230,126 @@ pub async fn handle_request_and_apply_store(
         }
         return Ok(payload.to_vec());
     }
+    // Special-case: intelligent function module buffer memory batch read (0x0601, sub 0x0000)
+    // Request: command(2le) subcommand(2le) start_addr:4le(bytes) byte_count:2le module_no:2le
+    // Response: buffer memory bytes (words little-endian)
+    // module_no is the start I/O number divided by 16 (e.g. 0x0004 for I/O 0x0040).
+    if command == 0x0601 && sub == 0x0000 {
+        if data.len() < 12 {
+            tracing::warn!(len = data.len(), "module buffer read request too short");
+            return Err(EndCodeError(END_CODE_REQUEST_LENGTH).into());
+        }
+        let start = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let byte_count = u16::from_le_bytes([data[8], data[9]]) as usize;
+        let module_no = u16::from_le_bytes([data[10], data[11]]);
+        if !(2..=MODULE_BUFFER_MAX_BYTES).contains(&byte_count)
+            || !byte_count.is_multiple_of(2)
+            || !start.is_multiple_of(2)
+        {
+            tracing::warn!(
+                start = %format!("0x{:X}", start),
+                byte_count,
+                "module buffer read out of range"
+            );
+            return Err(EndCodeError(END_CODE_POINTS_OUT_OF_RANGE).into());
+        }
+        let words = {
+            let s = store.read().await;
+            s.get_module_words(module_no << 4, start / 2, byte_count / 2)
+        };
+        let mut out: Vec<u8> = Vec::with_capacity(byte_count);
+        for w in words {
+            out.extend_from_slice(&w.to_le_bytes());
+        }
+        return Ok(out);
+    }
+    // Special-case: error history read (0x0626, sub 0x0000)
+    // Request: command(2le) subcommand(2le) start_entry:2le entry_count:2le
+    // Response: returned_count:2le, then per entry code:2le timestamp:6 (BCD
+    // yy mm dd hh mm ss) detail:2le. Fewer entries than requested are
+    // returned when the history is shorter.
+    if command == 0x0626 && sub == 0x0000 {
+        if data.len() < 8 {
+            tracing::warn!(len = data.len(), "error log read request too short");
+            return Err(EndCodeError(END_CODE_REQUEST_LENGTH).into());
+        }
+        let start = u16::from_le_bytes([data[4], data[5]]) as usize;
+        let count = u16::from_le_bytes([data[6], data[7]]) as usize;
+        let s = store.read().await;
+        let entries = s.error_log(start, count);
+        let mut out: Vec<u8> = Vec::with_capacity(2 + entries.len() * 10);
+        out.extend_from_slice(&u16::try_from(entries.len()).unwrap_or(0).to_le_bytes());
+        for e in entries {
+            out.extend_from_slice(&e.code.to_le_bytes());
+            out.extend_from_slice(&e.timestamp);
+            out.extend_from_slice(&e.detail.to_le_bytes());
+        }
+        return Ok(out);
+    }
+    // Special-case: random write in bit units (0x1402, sub 0x0001 / 0x0003)
+    // Request: command(2le) subcommand(2le) point_count:1 then per point
+    //   sub 0x0001: device_no:3le device_code:1 on_off:1
+    //   sub 0x0003: device_no:4le device_code:2le on_off:2le
+    // Response: empty payload
+    if command == 0x1402 && (sub == 0x0001 || sub == 0x0003) {
+        let (addr_len, code_len, value_len) = if sub == 0x0003 { (4, 2, 2) } else { (3, 1, 1) };
+        let point_len = addr_len + code_len + value_len;
+        let Some(&points) = data.get(4) else {
+            tracing::warn!("random bit write request has no point count");
+            return Err(EndCodeError(END_CODE_REQUEST_LENGTH).into());
+        };
+        let points = usize::from(points);
+        if points == 0 {
+            tracing::warn!("random bit write request with zero points");
+            return Err(EndCodeError(END_CODE_POINTS_OUT_OF_RANGE).into());
+        }
+        if data.len() < 5 + points * point_len {
+            tracing::warn!(
+                points,
+                len = data.len(),
+                "random bit write request too short"
+            );
+            return Err(EndCodeError(END_CODE_REQUEST_LENGTH).into());
+        }
+        let mut s = store.write().await;
+        for p in data[5..5 + points * point_len].chunks_exact(point_len) {
+            let mut addr_bytes = [0u8; 4];
+            addr_bytes[..addr_len].copy_from_slice(&p[..addr_len]);
+            let addr = u32::from_le_bytes(addr_bytes) as usize;
+            let dev_code = p[addr_len];
+            let on = p[addr_len + code_len..].iter().any(|b| *b != 0);
+            let key_literal = format!("0x{:02X}", dev_code);
+            tracing::info!(key = %key_literal, addr, on, "apply random write_bit to store");
+            s.set_words(&key_literal, addr, &[u16::from(on)]);
+        }
+        return Ok(Vec::new());
+    }
+    // The masked word write is mock-only (see `handle_masked_word_write`):
+    // without a registry spec a PLC does not know the pair.
+    if command == MASKED_WORD_WRITE_COMMAND
+        && sub == MASKED_WORD_WRITE_SUBCOMMAND
+        && registry_opt
+            .and_then(|reg| reg.find_by_code_and_sub(command, sub, None))
+            .is_none()
+    {
+        tracing::warn!("masked word write is not enabled on this mock");
+        return Err(EndCodeError(END_CODE_COMMAND_NOT_SUPPORTED).into());
+    }
+    // Reject batch read/write requests exceeding the per-frame point limit the
+    // same way a PLC does (end-code 0xC051) instead of serving them.
+    if command == 0x0401 || command == 0x1401 {
+        if let Some((_start, _dev, count, _off)) = read_start_and_device_and_count() {
+            let limit = if sub == 0x0001 || sub == 0x0003 {
+                MAX_BIT_POINTS
+            } else {
+                MAX_WORD_POINTS
+            };
+            if count > limit {
+                tracing::warn!(count, limit, "batch request exceeds point limit");
+                return Err(EndCodeError(END_CODE_POINTS_OUT_OF_RANGE).into());
+            }
+        }
+    }
     // Log monitor timer if present (for subheader+MC3E requests)
     tracing::debug!(
         monitor_timer = req.monitoring_timer,
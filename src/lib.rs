@@ -1,7 +1,10 @@
 //! Lightweight mock PLC server crate (module entry)
 
+pub mod ascii;
 pub mod device_map;
 pub mod handler;
+pub mod mc1e;
 pub mod server;
+pub mod transcript;
 
 pub use server::MockServer;
@@ -0,0 +1,163 @@
+//! MC1E (A-compatible 1E frame, binary code) support.
+//!
+//! 1E frames carry no network/PC access route and have no fixed subheader, so
+//! they cannot be told apart from a subheader-less MC3E frame by their first
+//! bytes. The mock therefore serves them on a dedicated listener
+//! (`MockServer::run_mc1e_listener`) sharing the same `DeviceMap`.
+//!
+//! Only the batch read/write commands are implemented:
+//!
+//! | subheader | command                 |
+//! |-----------|-------------------------|
+//! | 0x00      | batch read, bit units   |
+//! | 0x01      | batch read, word units  |
+//! | 0x02      | batch write, bit units  |
+//! | 0x03      | batch write, word units |
+//!
+//! Request:  subheader(1) pc_no(1) monitoring_timer(2le) head_device(4le)
+//!           device_code(2le, ASCII symbol e.g. "D " = 0x4420) points(1, 0 = 256)
+//!           0x00(1) [write data]
+//! Response: subheader | 0x80 (1) end_code(1) [read data]
+//!
+//! Bit data is nibble-packed high-first like the 3E/4E bit commands.
+
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::device_map::DeviceMap;
+
+/// Length of the fixed part of a 1E batch request.
+pub const HEADER_LEN: usize = 12;
+/// Normal completion.
+pub const END_CODE_OK: u8 = 0x00;
+/// Command (subheader) not supported by the mock.
+pub const END_CODE_UNSUPPORTED: u8 = 0x50;
+/// Device designation error (unknown device code).
+pub const END_CODE_DEVICE: u8 = 0x56;
+
+/// Total length of the 1E request at the start of `buf`.
+///
+/// Returns `Ok(None)` while the header is incomplete and an error for a
+/// subheader the mock does not implement (the stream cannot be resynchronised).
+pub fn frame_len(buf: &[u8]) -> anyhow::Result<Option<usize>> {
+    let Some(&sub) = buf.first() else {
+        return Ok(None);
+    };
+    if sub > 0x03 {
+        anyhow::bail!("unsupported MC1E subheader 0x{:02X}", sub);
+    }
+    if buf.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    let count = points(buf[10]);
+    let data_len = match sub {
+        0x02 => count.div_ceil(2),
+        0x03 => count * 2,
+        _ => 0,
+    };
+    Ok(Some(HEADER_LEN + data_len))
+}
+
+/// Device symbol from a 1E device code, e.g. 0x4420 -> "D", 0x544E -> "TN".
+pub fn device_symbol(code: u16) -> String {
+    let [lo, hi] = code.to_le_bytes();
+    [hi, lo]
+        .iter()
+        .filter(|b| **b != b' ')
+        .map(|b| *b as char)
+        .collect()
+}
+
+fn points(b: u8) -> usize {
+    if b == 0 {
+        256
+    } else {
+        usize::from(b)
+    }
+}
+
+/// Handle one complete 1E request (as delimited by `frame_len`) and return
+/// the response frame.
+pub async fn handle_frame(store: &Arc<RwLock<DeviceMap>>, frame: &[u8]) -> Vec<u8> {
+    let sub = frame[0];
+    let mut out: Vec<u8> = vec![sub | 0x80];
+    let head = u32::from_le_bytes([frame[4], frame[5], frame[6], frame[7]]) as usize;
+    let code = u16::from_le_bytes([frame[8], frame[9]]);
+    let count = points(frame[10]);
+    let symbol = device_symbol(code);
+    let Some(dev) = melsec_mc::device::device_by_symbol(&symbol) else {
+        tracing::warn!(code = %format!("0x{:04X}", code), "unknown MC1E device code");
+        out.push(END_CODE_DEVICE);
+        return out;
+    };
+    let key = format!("0x{:02X}", dev.device_code_q());
+    let data = &frame[HEADER_LEN..];
+    match sub {
+        0x00 => {
+            let bits = store.read().await.get_words(&key, head, count);
+            out.push(END_CODE_OK);
+            for pair in bits.chunks(2) {
+                let hi = u8::from(pair[0] != 0);
+                let lo = u8::from(pair.get(1).is_some_and(|v| *v != 0));
+                out.push((hi << 4) | lo);
+            }
+        }
+        0x01 => {
+            let words = store.read().await.get_words(&key, head, count);
+            out.push(END_CODE_OK);
+            for w in words {
+                out.extend_from_slice(&w.to_le_bytes());
+            }
+        }
+        0x02 => {
+            let bits: Vec<u16> = (0..count)
+                .map(|i| {
+                    let b = data[i / 2];
+                    let v = if i % 2 == 0 { b >> 4 } else { b & 0x0F };
+                    u16::from(v != 0)
+                })
+                .collect();
+            tracing::info!(key = %key, start = head, bits = ?bits, "apply mc1e write_bits to store");
+            store.write().await.set_words(&key, head, &bits);
+            out.push(END_CODE_OK);
+        }
+        0x03 => {
+            let words: Vec<u16> = data
+                .chunks_exact(2)
+                .take(count)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            tracing::info!(key = %key, start = head, words = ?words, "apply mc1e write_words to store");
+            store.write().await.set_words(&key, head, &words);
+            out.push(END_CODE_OK);
+        }
+        _ => out.push(END_CODE_UNSUPPORTED),
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_len_accounts_for_write_data() {
+        let mut hdr = vec![0x03u8, 0xFF, 0x0A, 0x00, 0, 0, 0, 0, 0x20, 0x44, 3, 0x00];
+        assert_eq!(frame_len(&hdr[..5]).unwrap(), None);
+        assert_eq!(frame_len(&hdr).unwrap(), Some(HEADER_LEN + 6));
+        hdr[0] = 0x02;
+        assert_eq!(frame_len(&hdr).unwrap(), Some(HEADER_LEN + 2));
+        hdr[0] = 0x01;
+        hdr[10] = 0;
+        assert_eq!(frame_len(&hdr).unwrap(), Some(HEADER_LEN));
+        hdr[0] = 0x17;
+        assert!(frame_len(&hdr).is_err());
+    }
+
+    #[test]
+    fn device_symbol_strips_padding() {
+        assert_eq!(device_symbol(0x4420), "D");
+        assert_eq!(device_symbol(0x4D20), "M");
+        assert_eq!(device_symbol(0x544E), "TN");
+    }
+}
@@ -1,6 +1,8 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 use crate::device_map::{DeviceMap, Word};
 
@@ -44,7 +46,7 @@ mod tests {
     }
 }
 // Simple HTTP admin API (minimal, no external HTTP framework) for state injection
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::UdpSocket;
 
 #[derive(Clone)]
@@ -57,6 +59,29 @@ use tokio::net::UdpSocket;
 /// Mock は受信したフレームから MC3E/MC4E を自動判定し、応答も同じフォーマットで返します。
 pub struct MockServer {
     pub store: Arc<RwLock<DeviceMap>>,
+    /// Command definitions used to dispatch requests. `None` uses the global
+    /// `CommandRegistry` (embedded `commands.toml`); when set, it is the
+    /// complete command set and other commands are rejected with 0xC059.
+    pub registry: Option<Arc<melsec_mc::command_registry::CommandRegistry>>,
+    /// Device definitions consulted first when `set_words`/`get_words`
+    /// resolve a device symbol.
+    pub devices: Option<Arc<melsec_mc::device_registry::DeviceRegistry>>,
+    /// Error code definitions used to describe returned end-codes.
+    pub error_codes: Option<Arc<melsec_mc::error_codes::ErrorRegistry>>,
+    /// End-codes queued by `inject_end_code`, consumed one per request.
+    injected_end_codes: Arc<Mutex<VecDeque<u16>>>,
+    /// Payloads queued by `inject_read_data`, consumed one per batch read.
+    injected_read_data: Arc<Mutex<VecDeque<Vec<u8>>>>,
+    /// Delay applied before every TCP/UDP response (`set_response_delay`).
+    response_delay: Arc<Mutex<Duration>>,
+    /// Recorded responses replayed before the handler runs (`from_transcript`).
+    transcript: Option<Arc<Mutex<crate::transcript::Transcript>>>,
+    /// Connections still to be reset right after accept (`refuse_next_connects`).
+    refused_connects: Arc<AtomicUsize>,
+    /// Communication data code of the MC3E/MC4E TCP listener (`with_data_code`).
+    data_code: crate::ascii::DataCode,
+    /// Serve the mock-only masked word write (`with_masked_word_write`).
+    masked_word_write: bool,
 }
 
 impl Default for MockServer {
@@ -100,6 +125,16 @@ impl MockServer {
         }
         Self {
             store: Arc::new(RwLock::new(dm)),
+            registry: None,
+            devices: None,
+            error_codes: None,
+            injected_end_codes: Arc::new(Mutex::new(VecDeque::new())),
+            injected_read_data: Arc::new(Mutex::new(VecDeque::new())),
+            response_delay: Arc::new(Mutex::new(Duration::ZERO)),
+            transcript: None,
+            refused_connects: Arc::new(AtomicUsize::new(0)),
+            data_code: crate::ascii::DataCode::Binary,
+            masked_word_write: false,
         }
     }
 
@@ -107,6 +142,84 @@ impl MockServer {
         Self::new_with_assignment(None)
     }
 
+    /// Select what reads of never-written words return (`ReadDefault::Zero`
+    /// by default). Bit devices read ON when the default word is non-zero.
+    pub async fn with_read_default(self, read_default: crate::device_map::ReadDefault) -> Self {
+        self.set_read_default(read_default).await;
+        self
+    }
+
+    /// Configure the MC3E/MC4E TCP listener for ASCII or binary (default)
+    /// communication data code, like the port setting of a PLC. See
+    /// `crate::ascii` for what the ASCII mode translates. UDP, Unix socket and
+    /// MC1E listeners stay binary.
+    pub fn with_data_code(mut self, data_code: crate::ascii::DataCode) -> Self {
+        self.data_code = data_code;
+        self
+    }
+
+    /// Serve the mock-only masked word write (0x1402/0x0010, see
+    /// `crate::handler::handle_masked_word_write`). Off by default, so the
+    /// pair is answered with end-code 0xC059 like on a real PLC.
+    pub fn with_masked_word_write(mut self, enabled: bool) -> Self {
+        self.masked_word_write = enabled;
+        self
+    }
+
+    /// Change the read default of a running server (see `with_read_default`).
+    pub async fn set_read_default(&self, read_default: crate::device_map::ReadDefault) {
+        self.store.write().await.set_read_default(read_default);
+    }
+
+    /// Create a MockServer which answers using the definitions found in `dir`
+    /// (`device.toml`, `commands.toml`, `error_codes.toml`) so the mock parses
+    /// requests with the same command set the client builds them from.
+    ///
+    /// All three files must exist and parse. The bundle's commands are the
+    /// complete command set (anything else is answered with end-code 0xC059),
+    /// its device symbols are resolved by `set_words`/`get_words` before the
+    /// global `melsec_mc` tables, and its error codes describe returned
+    /// end-codes in the log.
+    pub fn from_define_dir<P: AsRef<std::path::Path>>(dir: P) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        for name in ["device.toml", "commands.toml", "error_codes.toml"] {
+            let p = dir.join(name);
+            if !p.is_file() {
+                anyhow::bail!("define file not found: {}", p.display());
+            }
+        }
+        let commands =
+            melsec_mc::command_registry::CommandRegistry::from_path(dir.join("commands.toml"))
+                .map_err(|e| {
+                    anyhow::anyhow!("invalid commands.toml in {}: {}", dir.display(), e)
+                })?;
+        let devices =
+            melsec_mc::device_registry::DeviceRegistry::from_path(dir.join("device.toml"))
+                .map_err(|e| anyhow::anyhow!("invalid device.toml in {}: {}", dir.display(), e))?;
+        let error_codes =
+            melsec_mc::error_codes::ErrorRegistry::from_path(dir.join("error_codes.toml"))
+                .map_err(|e| {
+                    anyhow::anyhow!("invalid error_codes.toml in {}: {}", dir.display(), e)
+                })?;
+        tracing::info!(dir = %dir.display(), "loaded mock definitions from define dir");
+        let mut server = Self::new();
+        server.registry = Some(Arc::new(commands));
+        server.devices = Some(Arc::new(devices));
+        server.error_codes = Some(Arc::new(error_codes));
+        Ok(server)
+    }
+
+    /// Create a MockServer which answers captured requests with the responses
+    /// recorded in a transcript file (see `crate::transcript` for the schema).
+    /// Requests missing from the transcript are handled against the store as usual.
+    pub fn from_transcript<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<Self> {
+        let transcript = crate::transcript::Transcript::from_path(path.as_ref())?;
+        tracing::info!(path = %path.as_ref().display(), responses = transcript.len(), "loaded mock transcript");
+        let mut server = Self::new();
+        server.transcript = Some(Arc::new(Mutex::new(transcript)));
+        Ok(server)
+    }
+
     // (old wrapper `build_mc_response_bytes` removed) Use
     // `build_mc_response_from_request` directly when constructing responses.
 
@@ -139,6 +252,54 @@ impl MockServer {
         req: &melsec_mc::request::McRequest,
         resp_data: &[u8],
         format: melsec_mc::mc_define::McFrameFormat,
+    ) -> Vec<u8> {
+        Self::build_mc_frame_from_request(req, 0x0000, resp_data, format)
+    }
+
+    /// Build an error response for `req`: the non-zero end-code is followed by
+    /// the error information block (access route of the request, command and
+    /// subcommand) like a real PLC does.
+    fn build_mc_error_response_from_request(
+        req: &melsec_mc::request::McRequest,
+        end_code: u16,
+        format: melsec_mc::mc_define::McFrameFormat,
+    ) -> Vec<u8> {
+        let mut info: Vec<u8> = Vec::new();
+        info.extend_from_slice(&req.access_route.to_bytes());
+        let cmd_len = req.request_data.len().min(4);
+        info.extend_from_slice(&req.request_data[..cmd_len]);
+        Self::build_mc_frame_from_request(req, end_code, &info, format)
+    }
+
+    /// Build the 0x0050 error response sent for bytes that cannot be parsed
+    /// as a request. The format follows the subheader at the start of `buf`
+    /// (MC4E keeps its serial); the access route is the default one.
+    fn build_frame_error_response(buf: &[u8]) -> Vec<u8> {
+        let err_code: u16 = 0x0050;
+        let mut out: Vec<u8> = Vec::new();
+        if buf.get(..2) == Some(&melsec_mc::mc_define::MC_SUBHEADER_REQUEST[..]) {
+            let serial = if buf.len() >= 4 {
+                u16::from_le_bytes([buf[2], buf[3]])
+            } else {
+                0u16
+            };
+            out.extend_from_slice(&melsec_mc::mc_define::MC_SUBHEADER_RESPONSE);
+            out.extend_from_slice(&serial.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+        } else {
+            out.extend_from_slice(&[0xD0u8, 0x00u8]);
+        }
+        out.extend_from_slice(&melsec_mc::mc_define::AccessRoute::default().to_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&err_code.to_le_bytes());
+        out
+    }
+
+    fn build_mc_frame_from_request(
+        req: &melsec_mc::request::McRequest,
+        end_code: u16,
+        resp_data: &[u8],
+        format: melsec_mc::mc_define::McFrameFormat,
     ) -> Vec<u8> {
         let mut out: Vec<u8> = Vec::new();
         match format {
@@ -149,7 +310,7 @@ impl MockServer {
                 out.extend_from_slice(&req.access_route.to_bytes());
                 let data_len = u16::try_from(resp_data.len() + 2).unwrap_or(2);
                 out.extend_from_slice(&data_len.to_le_bytes());
-                out.extend_from_slice(&0u16.to_le_bytes());
+                out.extend_from_slice(&end_code.to_le_bytes());
                 out.extend_from_slice(resp_data);
             }
             melsec_mc::mc_define::McFrameFormat::MC3E => {
@@ -157,20 +318,154 @@ impl MockServer {
                 out.extend_from_slice(&req.access_route.to_bytes());
                 let data_len = u16::try_from(resp_data.len() + 2).unwrap_or(2);
                 out.extend_from_slice(&data_len.to_le_bytes());
-                out.extend_from_slice(&0u16.to_le_bytes());
+                out.extend_from_slice(&end_code.to_le_bytes());
                 out.extend_from_slice(resp_data);
             }
         }
         out
     }
 
+    /// Produce the complete response frame for a parsed request. A queued
+    /// injected end-code takes precedence; otherwise the request is dispatched
+    /// to the handler and handler `EndCodeError`s become error responses.
+    async fn respond_to_request(
+        &self,
+        frame: &[u8],
+        mc_req: &melsec_mc::request::McRequest,
+    ) -> Vec<u8> {
+        let fmt = Self::detect_format_from_frame(frame);
+        let delay = *self.response_delay.lock().await;
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        if let Some(end_code) = self.injected_end_codes.lock().await.pop_front() {
+            tracing::info!(end_code = %format!("0x{:04X}", end_code), "returning injected end-code");
+            return Self::build_mc_error_response_from_request(mc_req, end_code, fmt);
+        }
+        if let Some(transcript) = &self.transcript {
+            let recorded = transcript.lock().await.take_response(&mc_req.request_data);
+            match recorded {
+                Some((end_code, data)) => {
+                    tracing::debug!(end_code = %format!("0x{:04X}", end_code), "replaying recorded response");
+                    return Self::build_mc_frame_from_request(mc_req, end_code, &data, fmt);
+                }
+                None => tracing::warn!("request not found in transcript; handling against store"),
+            }
+        }
+        let masked = if self.masked_word_write {
+            crate::handler::handle_masked_word_write(&self.store, self.registry.as_deref(), mc_req)
+                .await
+        } else {
+            None
+        };
+        let result = match masked {
+            Some(result) => result,
+            None => {
+                crate::handler::handle_request_with_registry(
+                    &self.store,
+                    self.registry.as_deref(),
+                    mc_req,
+                )
+                .await
+            }
+        };
+        match result {
+            Ok(d) => {
+                let is_read = mc_req.request_data.get(..2) == Some(&0x0401u16.to_le_bytes()[..]);
+                if is_read {
+                    if let Some(altered) = self.injected_read_data.lock().await.pop_front() {
+                        tracing::info!(len = altered.len(), "returning injected read data");
+                        return Self::build_mc_response_from_request(mc_req, &altered, fmt);
+                    }
+                }
+                Self::build_mc_response_from_request(mc_req, &d, fmt)
+            }
+            Err(e) => {
+                if let Some(ec) = e.downcast_ref::<crate::handler::EndCodeError>() {
+                    let description = self
+                        .error_codes
+                        .as_ref()
+                        .and_then(|reg| reg.code_description(ec.0));
+                    tracing::info!(%e, description = ?description, "request rejected with end-code");
+                    return Self::build_mc_error_response_from_request(mc_req, ec.0, fmt);
+                }
+                tracing::error!(%e, "request handling failed");
+                Self::build_mc_response_from_request(mc_req, &[], fmt)
+            }
+        }
+    }
+
+    /// Queue `end_code` to be returned for the next request (TCP or UDP)
+    /// instead of handling it. Each queued code answers exactly one request
+    /// and leaves the store untouched.
+    pub async fn inject_end_code(&self, end_code: u16) {
+        self.injected_end_codes.lock().await.push_back(end_code);
+    }
+
+    /// Delay every subsequent response by `delay` to emulate a slow PLC.
+    /// `Duration::ZERO` (the default) answers immediately.
+    pub async fn set_response_delay(&self, delay: Duration) {
+        *self.response_delay.lock().await = delay;
+    }
+
+    /// Reset the next `n` TCP connections right after accepting them, to
+    /// emulate a PLC whose port is not ready yet (e.g. just after boot). A
+    /// bound listener cannot refuse the handshake itself, so the client sees
+    /// the reset (ECONNRESET / EOF) on its first read or write instead.
+    pub async fn refuse_next_connects(&self, n: usize) {
+        self.refused_connects.store(n, Ordering::SeqCst);
+    }
+
+    /// Consume one pending refusal; `true` if this connection must be reset.
+    fn take_refused_connect(&self) -> bool {
+        self.refused_connects
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    /// Queue `data` to replace the payload of the next successful batch read
+    /// (0x0401). The store is still read but its contents are not returned,
+    /// which lets tests simulate a PLC answering with altered values.
+    pub async fn inject_read_data(&self, data: Vec<u8>) {
+        self.injected_read_data.lock().await.push_back(data);
+    }
+
+    /// Resolve a device key, preferring symbols defined by the loaded
+    /// `device.toml` bundle over the global `melsec_mc` tables.
+    fn normalize_key(&self, key: &str, addr: usize) -> (String, usize) {
+        if let Some(ov) = self.devices.as_ref().and_then(|d| d.get_by_symbol(key)) {
+            match u8::try_from(ov.code) {
+                Ok(code) => return (format!("0x{:02X}", code), addr),
+                Err(_) => tracing::warn!(
+                    key = %key,
+                    code = %format!("0x{:X}", ov.code),
+                    "device.toml code does not fit a store key; using the global device tables"
+                ),
+            }
+        }
+        crate::device_map::normalize_key_addr(key, addr)
+    }
+
     /// Programmatic helpers for tests and programmatic control
     pub async fn set_words(&self, key: &str, addr: usize, words: &[Word]) {
-        let (rk, ra) = crate::device_map::normalize_key_addr(key, addr);
+        let (rk, ra) = self.normalize_key(key, addr);
         let mut store = self.store.write().await;
         store.set_words(&rk, ra, words);
     }
 
+    /// Seed the buffer memory of the intelligent function module at start I/O
+    /// number `module_io` (served by command 0x0601).
+    pub async fn set_module_words(&self, module_io: u16, addr: usize, words: &[Word]) {
+        let mut store = self.store.write().await;
+        store.set_module_words(module_io, addr, words);
+    }
+
+    /// Append a synthetic entry to the error history served by command 0x0626.
+    pub async fn push_error_log(&self, entry: crate::device_map::ErrorLogEntry) {
+        let mut store = self.store.write().await;
+        store.push_error_log(entry);
+    }
+
     /// Save the current device map to a snapshot file. Intended to be called on shutdown.
     pub async fn save_snapshot(&self, path: &str) -> anyhow::Result<()> {
         let s = self.store.read().await;
@@ -186,7 +481,7 @@ impl MockServer {
     }
 
     pub async fn get_words(&self, key: &str, addr: usize, count: usize) -> Vec<Word> {
-        let (rk, ra) = crate::device_map::normalize_key_addr(key, addr);
+        let (rk, ra) = self.normalize_key(key, addr);
         tracing::debug!(key = %key, addr = addr, rk = %rk, ra = ra, count = count, "mockserver.get_words called");
         let store = self.store.read().await;
         let res = store.get_words(&rk, ra, count);
@@ -213,266 +508,245 @@ impl MockServer {
     /// Run the listener accept loop using an already-bound TcpListener.
     pub async fn run_listener_on(self, listener: tokio::net::TcpListener) -> anyhow::Result<()> {
         loop {
-            let (socket, peer) = listener.accept().await?;
-            let store = self.store.clone();
+            let (mut socket, peer) = listener.accept().await?;
+            if self.take_refused_connect() {
+                tracing::info!(%peer, "refusing connection with RST");
+                reset_connection(socket);
+                continue;
+            }
+            let server = self.clone();
+            if server.data_code == crate::ascii::DataCode::Ascii {
+                tokio::spawn(async move { server.serve_ascii(socket, peer).await });
+                continue;
+            }
             tokio::spawn(async move {
                 tracing::info!(%peer, "accepted connection");
-                // Read buffer for incoming TCP data
-                let mut read_buf = vec![0u8; 4096];
-                let mut acc: Vec<u8> = Vec::new();
-                // determine TIM_AWAIT timeout (milliseconds) from env var
-                let tim_await_ms: u64 = std::env::var("MELSEC_MOCK_TIM_AWAIT_MS")
-                    .ok()
-                    .and_then(|s| s.parse::<u64>().ok())
-                    .unwrap_or(3000);
+                server
+                    .serve_frames(&mut socket, peer, Some(tim_await()))
+                    .await;
                 // per policy: always send RST on close to avoid TIME_WAIT on the peer side
-                // keep the socket in an Option so we can take ownership to set linger if needed
-                let mut socket = Some(socket);
-
-                // helper to set SO_LINGER=0 on the underlying socket and close it.
-                let close_with_rst = |sock_opt: &mut Option<tokio::net::TcpStream>| {
-                    if let Some(s) = sock_opt.take() {
-                        match s.into_std() {
-                            Ok(std_s) => {
-                                let _ = socket2::Socket::from(std_s)
-                                    .set_linger(Some(Duration::from_secs(0)));
-                            }
-                            Err(e) => {
-                                tracing::error!(%e, "failed to convert tokio TcpStream to std TcpStream for RST close")
-                            }
-                        }
-                    }
-                };
-                // whether we've successfully written at least one response to the peer
-                let mut _wrote_any = false;
-
-                loop {
-                    // read with timeout to implement TIM_AWAIT
-                    let read_fut = socket.as_mut().unwrap().read(&mut read_buf);
-                    match tokio::time::timeout(Duration::from_millis(tim_await_ms), read_fut).await
-                    {
-                        Ok(Ok(0)) => {
-                            tracing::info!(%peer, "connection closed by peer - forcing RST per policy");
-                            // peer closed the connection; force RST to avoid TIME_WAIT
-                            close_with_rst(&mut socket);
-                            return;
-                        }
-                        Ok(Ok(n)) => {
-                            acc.extend_from_slice(&read_buf[..n]);
-                            // try to parse frames from the accumulated buffer
-                            loop {
-                                match melsec_mc::mc_frame::detect_frame(&acc) {
-                                    Ok(Some((frame_len, _header_len, _serial_opt))) => {
-                                        if acc.len() < frame_len {
-                                            break;
-                                        }
-                                        let frame = acc.drain(..frame_len).collect::<Vec<u8>>();
-                                        tracing::debug!(len = frame.len(), frame = ?frame, "received tcp frame bytes");
-                                        match melsec_mc::request::McRequest::try_from_payload(
-                                            &frame,
-                                        ) {
-                                            Ok(mc_req) => {
-                                                let resp_data = match crate::handler::handle_request_and_apply_store(&store, &mc_req).await {
-                                                    Ok(d) => d,
-                                                    Err(e) => { tracing::error!(%e, "request handling failed"); vec![] }
-                                                };
-                                                let fmt = Self::detect_format_from_frame(&frame);
-                                                let out = Self::build_mc_response_from_request(
-                                                    &mc_req, &resp_data, fmt,
-                                                );
-                                                tracing::debug!(resp_len = out.len(), resp = ?out, "sending tcp response bytes");
-                                                let out_hex = out
-                                                    .iter()
-                                                    .map(|b| format!("{:02X}", b))
-                                                    .collect::<Vec<_>>()
-                                                    .join(" ");
-                                                let req_hex = frame
-                                                    .iter()
-                                                    .map(|b| format!("{:02X}", b))
-                                                    .collect::<Vec<_>>()
-                                                    .join(" ");
-                                                tracing::debug!(req = %req_hex, resp = %out_hex, "mockserver normal-response");
-                                                let write_res = socket
-                                                    .as_mut()
-                                                    .unwrap()
-                                                    .writable()
-                                                    .await
-                                                    .map_err(|e| anyhow::anyhow!(e))
-                                                    .and_then(|_| {
-                                                        match socket
-                                                            .as_mut()
-                                                            .unwrap()
-                                                            .try_write(&out)
-                                                        {
-                                                            Ok(_) => Ok(()),
-                                                            Err(e) => Err(anyhow::anyhow!(e)),
-                                                        }
-                                                    });
-                                                if write_res.is_ok() {
-                                                    _wrote_any = true;
-                                                } else if let Err(e) = write_res {
-                                                    tracing::error!(%e, "failed to write response to socket");
-                                                    // always force RST on write failure
-                                                    close_with_rst(&mut socket);
-                                                    return;
-                                                }
-                                            }
-                                            Err(e) => {
-                                                tracing::error!(%e, "failed to build McRequest from incoming frame");
-                                                tracing::debug!(acc_buf = ?acc, frame_len = frame.len(), "acc buffer / frame at parse-failure");
-                                                let acc_hex = acc
-                                                    .iter()
-                                                    .map(|b| format!("{:02X}", b))
-                                                    .collect::<Vec<_>>()
-                                                    .join(" ");
-                                                tracing::debug!(acc = %acc_hex, frame_len = frame.len(), "mockserver parse-failure");
-                                                // respond with protocol-appropriate error frame using the subheader
-                                                let err_code: u16 = 0x0050;
-                                                let subheader = if frame.len() >= 2 {
-                                                    [frame[0], frame[1]]
-                                                } else {
-                                                    [0x50u8, 0x00u8]
-                                                };
-                                                if subheader
-                                                    == melsec_mc::mc_define::MC_SUBHEADER_REQUEST
-                                                {
-                                                    let serial = if frame.len() >= 4 {
-                                                        u16::from_le_bytes([frame[2], frame[3]])
-                                                    } else {
-                                                        0u16
-                                                    };
-                                                    let mut out: Vec<u8> = Vec::new();
-                                                    out.extend_from_slice(&melsec_mc::mc_define::MC_SUBHEADER_RESPONSE);
-                                                    out.extend_from_slice(&serial.to_le_bytes());
-                                                    out.extend_from_slice(&0u16.to_le_bytes());
-                                                    out.extend_from_slice(&melsec_mc::mc_define::AccessRoute::default().to_bytes());
-                                                    out.extend_from_slice(&2u16.to_le_bytes());
-                                                    out.extend_from_slice(&err_code.to_le_bytes());
-                                                    tracing::debug!(error_out = ?out, "sending parse-error response bytes");
-                                                    let out_hex = out
-                                                        .iter()
-                                                        .map(|b| format!("{:02X}", b))
-                                                        .collect::<Vec<_>>()
-                                                        .join(" ");
-                                                    tracing::debug!(out = %out_hex, "mockserver parse-error response");
-                                                    let write_res = socket.as_mut().unwrap().writable().await.map_err(|e| anyhow::anyhow!(e)).and_then(|_| {
-                                                        match socket.as_mut().unwrap().try_write(&out) {
-                                                            Ok(n) => { tracing::debug!(written = n, "bytes_written for parse-error response"); Ok(()) },
-                                                            Err(e) => Err(anyhow::anyhow!(e)),
-                                                        }
-                                                    });
-                                                    if write_res.is_ok() {
-                                                        _wrote_any = true;
-                                                    }
-                                                } else {
-                                                    let mut out: Vec<u8> = Vec::new();
-                                                    out.extend_from_slice(&[0xD0u8, 0x00u8]);
-                                                    out.extend_from_slice(&melsec_mc::mc_define::AccessRoute::default().to_bytes());
-                                                    out.extend_from_slice(&2u16.to_le_bytes());
-                                                    out.extend_from_slice(&err_code.to_le_bytes());
-                                                    tracing::debug!(error_out = ?out, "sending parse-error response bytes (no subheader)");
-                                                    let out_hex = out
-                                                        .iter()
-                                                        .map(|b| format!("{:02X}", b))
-                                                        .collect::<Vec<_>>()
-                                                        .join(" ");
-                                                    tracing::debug!(out = %out_hex, "mockserver parse-error (no-subheader) response");
-                                                    let write_res = socket.as_mut().unwrap().writable().await.map_err(|e| anyhow::anyhow!(e)).and_then(|_| {
-                                                        match socket.as_mut().unwrap().try_write(&out) {
-                                                            Ok(n) => { tracing::debug!(written = n, "bytes_written for parse-error response (no subheader)"); Ok(()) },
-                                                            Err(e) => Err(anyhow::anyhow!(e)),
-                                                        }
-                                                    });
-                                                    if write_res.is_ok() {
-                                                        _wrote_any = true;
-                                                    }
-                                                }
-                                                // continue to next frame if any
-                                                continue;
-                                            }
-                                        }
+                reset_connection(socket);
+            });
+        }
+    }
+
+    /// Serve one ASCII-mode connection: ASCII frames are answered through
+    /// `respond_to_request`; anything else resets the connection without a
+    /// reply, as a port set to ASCII does not understand binary frames.
+    async fn serve_ascii(self, mut socket: tokio::net::TcpStream, peer: std::net::SocketAddr) {
+        tracing::info!(%peer, "accepted ascii connection");
+        let mut read_buf = vec![0u8; 4096];
+        let mut acc: Vec<u8> = Vec::new();
+        let tim_await = tim_await();
+        while read_chunk(&mut socket, &mut read_buf, &mut acc, Some(tim_await), peer).await {
+            loop {
+                match crate::ascii::frame_len(&acc) {
+                    Ok(Some(frame_len)) if acc.len() >= frame_len => {
+                        let frame = acc.drain(..frame_len).collect::<Vec<u8>>();
+                        tracing::debug!(frame = %String::from_utf8_lossy(&frame), "received ascii frame");
+                        let out = match crate::ascii::to_binary(&frame) {
+                            Ok(Ok(req)) => {
+                                match melsec_mc::request::McRequest::try_from_payload(&req.frame) {
+                                    Ok(mc_req) => {
+                                        let resp =
+                                            self.respond_to_request(&req.frame, &mc_req).await;
+                                        crate::ascii::to_ascii(&req, &resp)
                                     }
-                                    Ok(None) => break,
                                     Err(e) => {
-                                        tracing::error!(%e, "detect_frame error");
-                                        tracing::debug!(acc_buf = ?acc, "acc buffer at detect_frame error");
-                                        let acc_hex = acc
-                                            .iter()
-                                            .map(|b| format!("{:02X}", b))
-                                            .collect::<Vec<_>>()
-                                            .join(" ");
-                                        tracing::debug!(acc = %acc_hex, "mockserver detect_frame-error acc");
-                                        // guess subheader and send error response
-                                        let err_code: u16 = 0x0050;
-                                        let mut out: Vec<u8> = Vec::new();
-                                        let subheader = if acc.len() >= 2 {
-                                            [acc[0], acc[1]]
-                                        } else {
-                                            [0x50u8, 0x00u8]
-                                        };
-                                        if subheader == melsec_mc::mc_define::MC_SUBHEADER_REQUEST {
-                                            let serial = if acc.len() >= 4 {
-                                                u16::from_le_bytes([acc[2], acc[3]])
-                                            } else {
-                                                0u16
-                                            };
-                                            out.extend_from_slice(
-                                                &melsec_mc::mc_define::MC_SUBHEADER_RESPONSE,
-                                            );
-                                            out.extend_from_slice(&serial.to_le_bytes());
-                                            out.extend_from_slice(&0u16.to_le_bytes());
-                                            out.extend_from_slice(
-                                                &melsec_mc::mc_define::AccessRoute::default()
-                                                    .to_bytes(),
-                                            );
-                                            out.extend_from_slice(&2u16.to_le_bytes());
-                                            out.extend_from_slice(&err_code.to_le_bytes());
-                                        } else {
-                                            out.extend_from_slice(&[0xD0u8, 0x00u8]);
-                                            out.extend_from_slice(
-                                                &melsec_mc::mc_define::AccessRoute::default()
-                                                    .to_bytes(),
-                                            );
-                                            out.extend_from_slice(&2u16.to_le_bytes());
-                                            out.extend_from_slice(&err_code.to_le_bytes());
-                                        }
-                                        tracing::debug!(error_out = ?out, "sending detect_frame-error response bytes");
-                                        let out_hex = out
-                                            .iter()
-                                            .map(|b| format!("{:02X}", b))
-                                            .collect::<Vec<_>>()
-                                            .join(" ");
-                                        tracing::debug!(out = %out_hex, "mockserver detect_frame-error out");
-                                        let write_res = socket.as_mut().unwrap().writable().await.map_err(|e| anyhow::anyhow!(e)).and_then(|_| {
-                                            match socket.as_mut().unwrap().try_write(&out) {
-                                                Ok(n) => { tracing::debug!(written = n, "bytes_written for detect_frame-error response"); Ok(()) },
-                                                Err(e) => Err(anyhow::anyhow!(e)),
-                                            }
-                                        });
-                                        if write_res.is_ok() {
-                                            _wrote_any = true;
-                                        }
-                                        // force RST on malformed frame handling to simplify peer state
-                                        close_with_rst(&mut socket);
+                                        tracing::error!(%e, "failed to build McRequest from translated ascii frame");
+                                        reset_connection(socket);
                                         return;
                                     }
                                 }
                             }
+                            Ok(Err(unsupported)) => unsupported,
+                            Err(e) => {
+                                tracing::error!(%e, "invalid ascii frame");
+                                reset_connection(socket);
+                                return;
+                            }
+                        };
+                        tracing::debug!(resp = %String::from_utf8_lossy(&out), "sending ascii response");
+                        if let Err(e) = socket.write_all(&out).await {
+                            tracing::error!(%e, "failed to write ascii response");
+                            reset_connection(socket);
+                            return;
+                        }
+                    }
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::warn!(%e, "non-ascii frame on ascii port; resetting connection");
+                        reset_connection(socket);
+                        return;
+                    }
+                }
+            }
+        }
+        reset_connection(socket);
+    }
+
+    /// Serve binary MC frames on one connection until it has to be closed:
+    /// the peer closed it, a read or write failed, `tim_await` expired or the
+    /// buffered bytes could not be delimited into a frame (answered with a
+    /// 0x0050 reply first). A delimited frame that fails to parse also gets a
+    /// 0x0050 reply but keeps the connection open. Closing is left to the
+    /// caller, so each transport picks its own close behaviour.
+    async fn serve_frames<S>(
+        &self,
+        socket: &mut S,
+        peer: impl std::fmt::Display + Copy,
+        tim_await: Option<Duration>,
+    ) where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut read_buf = vec![0u8; 4096];
+        let mut acc: Vec<u8> = Vec::new();
+        while read_chunk(socket, &mut read_buf, &mut acc, tim_await, peer).await {
+            // try to parse frames from the accumulated buffer
+            loop {
+                match melsec_mc::mc_frame::detect_frame(&acc) {
+                    Ok(Some((frame_len, _header_len, _serial_opt))) => {
+                        if acc.len() < frame_len {
+                            break;
                         }
-                        Ok(Err(e)) => {
-                            tracing::error!(%e, "read error");
-                            // always force RST on read error
-                            close_with_rst(&mut socket);
+                        let frame = acc.drain(..frame_len).collect::<Vec<u8>>();
+                        tracing::debug!(%peer, len = frame.len(), frame = ?frame, "received frame bytes");
+                        let out = match melsec_mc::request::McRequest::try_from_payload(&frame) {
+                            Ok(mc_req) => {
+                                let out = self.respond_to_request(&frame, &mc_req).await;
+                                tracing::debug!(req = %hex_bytes(&frame), resp = %hex_bytes(&out), "mockserver normal-response");
+                                out
+                            }
+                            Err(e) => {
+                                tracing::error!(%e, "failed to build McRequest from incoming frame");
+                                tracing::debug!(acc = %hex_bytes(&acc), frame_len = frame.len(), "mockserver parse-failure");
+                                // respond with protocol-appropriate error frame using the subheader
+                                Self::build_frame_error_response(&frame)
+                            }
+                        };
+                        if let Err(e) = socket.write_all(&out).await {
+                            tracing::error!(%e, "failed to write response to socket");
                             return;
                         }
-                        Err(_) => {
-                            tracing::info!(%peer, "connection idle in TIM_AWAIT for {}ms, forcing RST and closing", tim_await_ms);
-                            // Per policy, force RST even on TIM_AWAIT expiry
-                            close_with_rst(&mut socket);
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!(%e, "detect_frame error");
+                        tracing::debug!(acc = %hex_bytes(&acc), "mockserver detect_frame-error acc");
+                        // guess subheader and send error response
+                        let out = Self::build_frame_error_response(&acc);
+                        tracing::debug!(out = %hex_bytes(&out), "mockserver detect_frame-error out");
+                        let _ = socket.write_all(&out).await;
+                        // close on malformed frame handling to simplify peer state
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Start a TCP listener speaking the MC1E (1E frame, binary) protocol
+    /// against the same store. See `crate::mc1e` for the supported commands.
+    pub async fn run_mc1e_listener(self, bind: &str) -> anyhow::Result<()> {
+        tracing::info!(%bind, "mc1e mock server binding");
+        let listener = tokio::net::TcpListener::bind(bind).await?;
+        self.run_mc1e_listener_on(listener).await
+    }
+
+    /// Run the MC1E accept loop using an already-bound TcpListener. Refused
+    /// connects and the response delay apply as on the MC3E/MC4E listener;
+    /// connections are closed with an RST as well.
+    pub async fn run_mc1e_listener_on(
+        self,
+        listener: tokio::net::TcpListener,
+    ) -> anyhow::Result<()> {
+        loop {
+            let (mut socket, peer) = listener.accept().await?;
+            if self.take_refused_connect() {
+                tracing::info!(%peer, "refusing mc1e connection with RST");
+                reset_connection(socket);
+                continue;
+            }
+            let server = self.clone();
+            tokio::spawn(async move {
+                tracing::info!(%peer, "accepted mc1e connection");
+                server.serve_mc1e(&mut socket, peer).await;
+                reset_connection(socket);
+            });
+        }
+    }
+
+    /// Serve MC1E frames on one connection until it has to be closed.
+    async fn serve_mc1e(&self, socket: &mut tokio::net::TcpStream, peer: std::net::SocketAddr) {
+        let mut read_buf = vec![0u8; 4096];
+        let mut acc: Vec<u8> = Vec::new();
+        let tim_await = tim_await();
+        while read_chunk(socket, &mut read_buf, &mut acc, Some(tim_await), peer).await {
+            loop {
+                match crate::mc1e::frame_len(&acc) {
+                    Ok(Some(frame_len)) if acc.len() >= frame_len => {
+                        let frame = acc.drain(..frame_len).collect::<Vec<u8>>();
+                        tracing::debug!(len = frame.len(), frame = ?frame, "received mc1e frame bytes");
+                        let delay = *self.response_delay.lock().await;
+                        if !delay.is_zero() {
+                            tokio::time::sleep(delay).await;
+                        }
+                        let out = crate::mc1e::handle_frame(&self.store, &frame).await;
+                        if let Err(e) = socket.write_all(&out).await {
+                            tracing::error!(%e, "failed to write mc1e response");
                             return;
                         }
                     }
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::error!(%e, "mc1e frame error");
+                        let out = [acc[0] | 0x80, crate::mc1e::END_CODE_UNSUPPORTED];
+                        let _ = socket.write_all(&out).await;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Serve MC frames on a Unix domain socket at `path` for simulators on the
+    /// same host. A stale socket at `path` (one that refuses connections) is
+    /// removed first; a socket with a live listener or any other file there is
+    /// an error. Connections share the TCP listener's frame handling
+    /// (`serve_frames`) except that TIM_AWAIT is not applied.
+    #[cfg(all(unix, feature = "unix-socket"))]
+    pub async fn run_unix_listener<P: AsRef<std::path::Path>>(self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        tracing::info!(path = %path.display(), "unix mock server binding");
+        if melsec_mc::command_registry::CommandRegistry::global().is_none() {
+            if let Err(e) =
+                melsec_mc::command_registry::CommandRegistry::load_and_set_global_from_src()
+            {
+                tracing::warn!(%e, "failed to load command registry from src; proceeding without it");
+            }
+        }
+        match std::fs::symlink_metadata(path) {
+            Ok(meta) => {
+                use std::os::unix::fs::FileTypeExt;
+                if !meta.file_type().is_socket() {
+                    anyhow::bail!("refusing to replace {}: not a Unix socket", path.display());
+                }
+                // only a socket nobody is listening on any more is stale
+                if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                    anyhow::bail!("{} is in use by a running listener", path.display());
                 }
+                std::fs::remove_file(path)?;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        let listener = tokio::net::UnixListener::bind(path)?;
+        loop {
+            let (mut socket, _peer) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                tracing::info!("accepted unix connection");
+                server.serve_frames(&mut socket, "unix", None).await;
             });
         }
     }
@@ -511,16 +785,7 @@ impl MockServer {
                     continue;
                 }
             };
-            let resp_data =
-                match crate::handler::handle_request_and_apply_store(&self.store, &mc_req).await {
-                    Ok(d) => d,
-                    Err(e) => {
-                        tracing::error!(%e, "request handling failed (udp)");
-                        vec![]
-                    }
-                };
-            let fmt = Self::detect_format_from_frame(&frame);
-            let out = Self::build_mc_response_from_request(&mc_req, &resp_data, fmt);
+            let out = self.respond_to_request(&frame, &mc_req).await;
             tracing::debug!(resp_len = out.len(), resp = ?out, peer = %peer, "sending udp response bytes");
             if let Err(e) = socket.send_to(&out, &peer).await.map(|_| ()) {
                 tracing::error!(%e, "failed to send udp response");
@@ -528,3 +793,71 @@ impl MockServer {
         }
     }
 }
+
+/// TIM_AWAIT: how long a TCP connection may stay idle before the mock closes
+/// it (`MELSEC_MOCK_TIM_AWAIT_MS`, default 3000 ms).
+fn tim_await() -> Duration {
+    let ms = std::env::var("MELSEC_MOCK_TIM_AWAIT_MS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(3000);
+    Duration::from_millis(ms)
+}
+
+/// Read the next chunk from `socket` into `acc`. Returns `false` when the
+/// connection should be closed: the peer closed it, the read failed or it
+/// stayed idle for `tim_await` (when one is set).
+async fn read_chunk<S: AsyncRead + Unpin>(
+    socket: &mut S,
+    read_buf: &mut [u8],
+    acc: &mut Vec<u8>,
+    tim_await: Option<Duration>,
+    peer: impl std::fmt::Display,
+) -> bool {
+    let read = match tim_await {
+        Some(t) => match tokio::time::timeout(t, socket.read(read_buf)).await {
+            Ok(read) => read,
+            Err(_) => {
+                tracing::info!(%peer, "connection idle in TIM_AWAIT for {}ms, closing", t.as_millis());
+                return false;
+            }
+        },
+        None => socket.read(read_buf).await,
+    };
+    match read {
+        Ok(0) => {
+            tracing::info!(%peer, "connection closed by peer");
+            false
+        }
+        Ok(n) => {
+            acc.extend_from_slice(&read_buf[..n]);
+            true
+        }
+        Err(e) => {
+            tracing::error!(%e, %peer, "read error");
+            false
+        }
+    }
+}
+
+/// Space-separated upper-case hex dump of `bytes` for debug logs.
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Close `stream` with SO_LINGER=0 so the peer gets an RST instead of the
+/// mock side lingering in TIME_WAIT.
+fn reset_connection(stream: tokio::net::TcpStream) {
+    match stream.into_std() {
+        Ok(std_s) => {
+            let _ = socket2::Socket::from(std_s).set_linger(Some(Duration::from_secs(0)));
+        }
+        Err(e) => {
+            tracing::error!(%e, "failed to convert tokio TcpStream to std TcpStream for RST close")
+        }
+    }
+}
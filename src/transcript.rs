@@ -0,0 +1,136 @@
+//! Replay of recorded client transcripts.
+//!
+//! A transcript is a JSON-lines file, one object per operation:
+//!
+//! ```text
+//! {"ts":"2026-10-15T09:30:00.123Z","op":"read_words","request":"5400...","response":"D400...","result":...}
+//! ```
+//!
+//! Only `request` and `response` (complete MC3E/MC4E frames as hex, spaces
+//! allowed) are used by the mock; other fields are informational. Requests
+//! are matched on their command/subcommand/payload bytes, so MC4E serials and
+//! access routes may differ between capture and replay. Identical requests
+//! recorded several times are answered in recorded order, the last response
+//! repeating once the others are used up.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+
+use anyhow::Context;
+
+/// Recorded end-code and response data for one request.
+pub type RecordedResponse = (u16, Vec<u8>);
+
+#[derive(Debug, Default)]
+pub struct Transcript {
+    responses: HashMap<Vec<u8>, VecDeque<RecordedResponse>>,
+}
+
+impl Transcript {
+    /// Load a transcript file. Lines without both `request` and `response`
+    /// (e.g. operations that failed before a response arrived) are skipped.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read transcript {}", path.display()))?;
+        let mut transcript = Self::default();
+        for (lineno, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: serde_json::Value = serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid JSON", path.display(), lineno + 1))?;
+            let (Some(req_hex), Some(resp_hex)) = (
+                entry.get("request").and_then(|v| v.as_str()),
+                entry.get("response").and_then(|v| v.as_str()),
+            ) else {
+                continue;
+            };
+            let ctx = || format!("{}:{}", path.display(), lineno + 1);
+            let req_frame = decode_hex(req_hex).with_context(ctx)?;
+            let resp_frame = decode_hex(resp_hex).with_context(ctx)?;
+            let req = melsec_mc::request::McRequest::try_from_payload(&req_frame)
+                .map_err(|e| anyhow::anyhow!("{}: invalid request frame: {}", ctx(), e))?;
+            let recorded = split_response(&resp_frame).with_context(ctx)?;
+            transcript
+                .responses
+                .entry(req.request_data.to_vec())
+                .or_default()
+                .push_back(recorded);
+        }
+        Ok(transcript)
+    }
+
+    /// Recorded response for `request_data`, or `None` if the request was
+    /// never captured.
+    pub fn take_response(&mut self, request_data: &[u8]) -> Option<RecordedResponse> {
+        let queue = self.responses.get_mut(request_data)?;
+        if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.responses.values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+}
+
+/// Split a recorded response frame into its end-code and response data.
+fn split_response(frame: &[u8]) -> anyhow::Result<RecordedResponse> {
+    // MC4E: subheader(2) serial(2) reserved(2) route(5) data_len(2) end_code(2)
+    // MC3E: subheader(2) route(5) data_len(2) end_code(2)
+    let off = match frame.get(..2) {
+        Some(sh) if sh == melsec_mc::mc_define::MC_SUBHEADER_RESPONSE => 13,
+        Some([0xD0, 0x00]) => 9,
+        _ => anyhow::bail!("response frame has no MC3E/MC4E response subheader"),
+    };
+    if frame.len() < off + 2 {
+        anyhow::bail!("response frame too short ({} bytes)", frame.len());
+    }
+    let end_code = u16::from_le_bytes([frame[off], frame[off + 1]]);
+    Ok((end_code, frame[off + 2..].to_vec()))
+}
+
+fn decode_hex(s: &str) -> anyhow::Result<Vec<u8>> {
+    let digits: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        anyhow::bail!("odd number of hex digits");
+    }
+    digits
+        .chunks_exact(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair)?;
+            u8::from_str_radix(pair, 16).with_context(|| format!("invalid hex byte {:?}", pair))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_hex_accepts_spaced_and_packed_input() {
+        assert_eq!(decode_hex("D0 00 0a").unwrap(), vec![0xD0, 0x00, 0x0A]);
+        assert_eq!(decode_hex("D0000A").unwrap(), vec![0xD0, 0x00, 0x0A]);
+        assert!(decode_hex("D00").is_err());
+        assert!(decode_hex("ZZ").is_err());
+    }
+
+    #[test]
+    fn split_response_handles_both_formats() {
+        let mc3e = [
+            0xD0, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x04, 0x00, 0x00, 0x00, 0x34, 0x12,
+        ];
+        assert_eq!(split_response(&mc3e).unwrap(), (0x0000, vec![0x34, 0x12]));
+        let mut mc4e = vec![0xD4, 0x00, 0x01, 0x00, 0x00, 0x00];
+        mc4e.extend_from_slice(&[0x00, 0xFF, 0xFF, 0x03, 0x00, 0x02, 0x00, 0x51, 0xC0]);
+        assert_eq!(split_response(&mc4e).unwrap(), (0xC051, vec![]));
+    }
+}
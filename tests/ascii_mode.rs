@@ -0,0 +1,91 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use melsec_mc_mock::ascii::DataCode;
+use melsec_mc_mock::MockServer;
+
+use common::{read_d_request, roundtrip, spawn_listener};
+
+/// MC3E ASCII request with the default route and monitoring timer 0x0010.
+fn ascii_request(body: &str) -> Vec<u8> {
+    format!("500000FF03FF00{:04X}0010{}", body.len() + 4, body).into_bytes()
+}
+
+/// Send `req` and read one MC3E ASCII response frame.
+async fn ascii_roundtrip(stream: &mut TcpStream, req: &[u8]) -> String {
+    stream.write_all(req).await.expect("write");
+    timeout(Duration::from_secs(2), async {
+        let mut frame = vec![0u8; 18];
+        stream.read_exact(&mut frame).await.expect("read header");
+        let len = usize::from_str_radix(std::str::from_utf8(&frame[14..18]).unwrap(), 16)
+            .expect("data_len");
+        frame.resize(18 + len, 0);
+        stream
+            .read_exact(&mut frame[18..])
+            .await
+            .expect("read data");
+        String::from_utf8(frame).expect("ascii response")
+    })
+    .await
+    .expect("timeout")
+}
+
+#[tokio::test]
+async fn ascii_port_answers_ascii_frames() {
+    let server = MockServer::new().with_data_code(DataCode::Ascii);
+    server.set_words("D", 100, &[0x1234u16, 0xABCD]).await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    // batch read D100..D101 in word units
+    let resp = ascii_roundtrip(&mut stream, &ascii_request("04010000D*0001000002")).await;
+    assert_eq!(resp, "D00000FF03FF00000C00001234ABCD");
+
+    // batch write M10..M12 = ON, OFF, ON, then read them back in bit units
+    let resp = ascii_roundtrip(&mut stream, &ascii_request("14010001M*0000100003101")).await;
+    assert_eq!(resp, "D00000FF03FF0000040000");
+    assert_eq!(server.get_words("M", 10, 3).await, vec![1, 0, 1]);
+    let resp = ascii_roundtrip(&mut stream, &ascii_request("04010001M*0000100003")).await;
+    assert_eq!(resp, "D00000FF03FF0000070000101");
+
+    // commands without an ASCII translation are rejected, not misread
+    let resp = ascii_roundtrip(&mut stream, &ascii_request("06190000AB")).await;
+    assert_eq!(resp, "D00000FF03FF000016C05900FF03FF0006190000");
+}
+
+#[tokio::test]
+async fn ascii_port_does_not_answer_binary_frames() {
+    let server = MockServer::new().with_data_code(DataCode::Ascii);
+    let addr = spawn_listener(server).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    stream
+        .write_all(&read_d_request(0, 1).build())
+        .await
+        .expect("write");
+    let mut buf = [0u8; 64];
+    match timeout(Duration::from_secs(2), stream.read(&mut buf))
+        .await
+        .expect("reset arrives")
+    {
+        Ok(0) | Err(_) => {}
+        Ok(n) => panic!("ascii port answered a binary frame: {:02X?}", &buf[..n]),
+    }
+}
+
+#[tokio::test]
+async fn binary_port_answers_binary_frames() {
+    let server = MockServer::new().with_data_code(DataCode::Binary);
+    server.set_words("D", 100, &[0x1234u16]).await;
+    let addr = spawn_listener(server).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    let (end_code, data) = roundtrip(&mut stream, &read_d_request(100, 1).build()).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0x34, 0x12]);
+}
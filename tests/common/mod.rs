@@ -0,0 +1,156 @@
+//! Fixtures shared by the integration tests: request builders, listener
+//! spawning and frame-level round trips.
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+
+use melsec_mc::mc_define::{AccessRoute, MC_SUBHEADER_REQUEST, MC_SUBHEADER_RESPONSE};
+use melsec_mc::request::McRequest;
+use melsec_mc_mock::MockServer;
+
+/// Request data for a batch word read of D (0x0401/0x0000), MC3E device
+/// layout: start 3le, device code 1, count 2le.
+pub fn read_d_data(start: u32, count: u16) -> Vec<u8> {
+    let mut req: Vec<u8> = Vec::new();
+    req.extend_from_slice(&0x0401u16.to_le_bytes());
+    req.extend_from_slice(&0x0000u16.to_le_bytes());
+    req.extend_from_slice(&start.to_le_bytes()[..3]);
+    req.push(0xA8u8); // D
+    req.extend_from_slice(&count.to_le_bytes());
+    req
+}
+
+pub fn read_d_request(start: u32, count: u16) -> McRequest {
+    request(&read_d_data(start, count))
+}
+
+/// Request carrying `request_data` (command 2le, subcommand 2le, body).
+pub fn request(request_data: &[u8]) -> McRequest {
+    McRequest::new()
+        .try_with_request_data(request_data)
+        .expect("build request")
+}
+
+/// Complete MC4E request frame carrying `request_data` under `serial`.
+pub fn mc4e_frame(serial: u16, request_data: &[u8]) -> Vec<u8> {
+    let mut frame: Vec<u8> = Vec::new();
+    frame.extend_from_slice(&MC_SUBHEADER_REQUEST);
+    frame.extend_from_slice(&serial.to_le_bytes());
+    frame.extend_from_slice(&0u16.to_le_bytes());
+    frame.extend_from_slice(&AccessRoute::default().to_bytes());
+    frame.extend_from_slice(&u16::try_from(request_data.len() + 2).unwrap().to_le_bytes());
+    frame.extend_from_slice(&0x0010u16.to_le_bytes()); // monitoring timer
+    frame.extend_from_slice(request_data);
+    frame
+}
+
+pub fn mc4e_read_d(serial: u16, start: u32, count: u16) -> Vec<u8> {
+    mc4e_frame(serial, &read_d_data(start, count))
+}
+
+/// Split a response frame into (end_code, data) for both MC4E and MC3E layouts.
+pub fn end_code_and_data(frame: &[u8]) -> (u16, Vec<u8>) {
+    let off = if frame[..2] == MC_SUBHEADER_RESPONSE {
+        13
+    } else {
+        9
+    };
+    (
+        u16::from_le_bytes([frame[off], frame[off + 1]]),
+        frame[off + 2..].to_vec(),
+    )
+}
+
+pub fn hex(b: &[u8]) -> String {
+    b.iter()
+        .map(|x| format!("{:02X}", x))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Run `server`'s MC3E/MC4E listener on an ephemeral port.
+pub async fn spawn_listener(server: MockServer) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local_addr");
+    tokio::spawn(async move {
+        let _ = server.run_listener_on(listener).await;
+    });
+    addr
+}
+
+/// Run `server`'s MC1E listener on an ephemeral port.
+pub async fn spawn_mc1e_listener(server: MockServer) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local_addr");
+    tokio::spawn(async move {
+        let _ = server.run_mc1e_listener_on(listener).await;
+    });
+    addr
+}
+
+/// Read exactly one MC3E/MC4E response frame (never past its end, so
+/// pipelined responses stay in the stream).
+pub async fn read_response_frame<S: AsyncRead + Unpin>(stream: &mut S) -> Vec<u8> {
+    timeout(Duration::from_secs(2), async {
+        let mut frame = vec![0u8; 2];
+        stream.read_exact(&mut frame).await.expect("read subheader");
+        // header up to and including data_len
+        let header_len = if frame[..2] == MC_SUBHEADER_RESPONSE {
+            13
+        } else {
+            9
+        };
+        frame.resize(header_len, 0);
+        stream
+            .read_exact(&mut frame[2..])
+            .await
+            .expect("read header");
+        let data_len = usize::from(u16::from_le_bytes([
+            frame[header_len - 2],
+            frame[header_len - 1],
+        ]));
+        frame.resize(header_len + data_len, 0);
+        stream
+            .read_exact(&mut frame[header_len..])
+            .await
+            .expect("read data");
+        frame
+    })
+    .await
+    .expect("timeout")
+}
+
+/// Send `req` and return the complete response frame.
+pub async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, req: &[u8]) -> Vec<u8> {
+    stream.write_all(req).await.expect("write");
+    read_response_frame(stream).await
+}
+
+/// Send `req` and return the (end_code, data) of the response.
+pub async fn roundtrip<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    req: &[u8],
+) -> (u16, Vec<u8>) {
+    end_code_and_data(&exchange(stream, req).await)
+}
+
+/// Send `req` and read exactly `resp_len` bytes back (for frames the MC
+/// response reader does not understand, e.g. MC1E).
+pub async fn roundtrip_exact<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    req: &[u8],
+    resp_len: usize,
+) -> Vec<u8> {
+    stream.write_all(req).await.expect("write");
+    let mut resp = vec![0u8; resp_len];
+    timeout(Duration::from_secs(2), stream.read_exact(&mut resp))
+        .await
+        .expect("timeout")
+        .expect("read");
+    resp
+}
@@ -0,0 +1,112 @@
+//! MC4E serial numbers under concurrency and 16-bit wraparound.
+//!
+//! Clients draw serials from a shared 16-bit counter that wraps from 0xFFFF
+//! to 0x0000, so a serial is reused after 65,536 requests. Two outstanding
+//! requests can only share a serial if one is still unanswered after 65,535
+//! later requests were issued (the wraparound window). The mock echoes the
+//! serial of each request on the connection it arrived on, so responses
+//! never cross connections even when serials collide.
+
+mod common;
+
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::TcpStream;
+
+use melsec_mc::mc_define::MC_SUBHEADER_RESPONSE;
+use melsec_mc_mock::MockServer;
+
+use common::{end_code_and_data, exchange, mc4e_read_d, spawn_listener};
+
+const CONNECTIONS: u16 = 16;
+const REQUESTS_PER_CONNECTION: u16 = 50;
+/// First serial handed out: the counter wraps halfway through the test.
+const FIRST_SERIAL: u16 = 0u16.wrapping_sub(CONNECTIONS * REQUESTS_PER_CONNECTION / 2);
+
+fn value_for(request: u16) -> u16 {
+    request ^ 0xA5A5
+}
+
+/// Send one read and check the response carries `serial` and `request`'s word.
+async fn read_checked(stream: &mut TcpStream, serial: u16, request: u16) {
+    let frame = exchange(stream, &mc4e_read_d(serial, u32::from(request), 1)).await;
+    assert_eq!(frame[..2], MC_SUBHEADER_RESPONSE);
+    assert_eq!(u16::from_le_bytes([frame[2], frame[3]]), serial);
+    let (end_code, data) = end_code_and_data(&frame);
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, value_for(request).to_le_bytes());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_requests_across_serial_wraparound_do_not_cross_talk() {
+    let server = MockServer::new();
+    // every request reads a distinct word; serials alone are not unique
+    let total = CONNECTIONS * REQUESTS_PER_CONNECTION;
+    for request in 0..total {
+        server
+            .set_words("D", usize::from(request), &[value_for(request)])
+            .await;
+    }
+    // keep many requests in flight at once across connections
+    server.set_response_delay(Duration::from_millis(5)).await;
+    let addr = spawn_listener(server.clone()).await;
+
+    let counter = Arc::new(AtomicU16::new(FIRST_SERIAL));
+    let next_request = Arc::new(AtomicU16::new(0));
+    let mut tasks = Vec::new();
+    for _ in 0..CONNECTIONS {
+        let counter = counter.clone();
+        let next_request = next_request.clone();
+        tasks.push(tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.expect("connect");
+            let mut serials = Vec::new();
+            for _ in 0..REQUESTS_PER_CONNECTION {
+                // like the client's global counter: fetch_add wraps at 0xFFFF
+                let serial = counter.fetch_add(1, Ordering::Relaxed);
+                let request = next_request.fetch_add(1, Ordering::Relaxed);
+                read_checked(&mut stream, serial, request).await;
+                serials.push(serial);
+            }
+            serials
+        }));
+    }
+    let mut seen = Vec::new();
+    for t in tasks {
+        seen.extend(t.await.expect("client task"));
+    }
+    // the run covered the wrap: both 0xFFFF and 0x0000 were issued
+    assert!(seen.contains(&0xFFFF));
+    assert!(seen.contains(&0x0000));
+    assert_eq!(
+        counter.load(Ordering::Relaxed),
+        FIRST_SERIAL.wrapping_add(total)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn colliding_serials_on_different_connections_get_their_own_data() {
+    let server = MockServer::new();
+    for request in 0..CONNECTIONS {
+        server
+            .set_words("D", usize::from(request), &[value_for(request)])
+            .await;
+    }
+    server.set_response_delay(Duration::from_millis(20)).await;
+    let addr = spawn_listener(server.clone()).await;
+
+    // all connections reuse 0xFFFF and then 0x0000 concurrently
+    let mut tasks = Vec::new();
+    for request in 0..CONNECTIONS {
+        tasks.push(tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.expect("connect");
+            for serial in [0xFFFFu16, 0x0000] {
+                read_checked(&mut stream, serial, request).await;
+            }
+        }));
+    }
+    for t in tasks {
+        t.await.expect("client task");
+    }
+}
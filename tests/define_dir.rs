@@ -0,0 +1,85 @@
+use melsec_mc::command_registry::CommandRegistry;
+use melsec_mc::request::McRequest;
+use melsec_mc_mock::handler::{self, EndCodeError};
+use melsec_mc_mock::MockServer;
+
+fn scratch_dir(label: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "melsec_mock_define_{}_{}",
+        label,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create scratch dir");
+    dir
+}
+
+#[test]
+fn from_define_dir_reports_missing_file() {
+    let dir = scratch_dir("missing");
+    // only commands.toml present -> device.toml is reported as missing
+    std::fs::write(dir.join("commands.toml"), "").expect("write commands.toml");
+
+    let err = match MockServer::from_define_dir(&dir) {
+        Ok(_) => panic!("expected missing define file error"),
+        Err(e) => e.to_string(),
+    };
+    assert!(err.contains("device.toml"), "unexpected error: {}", err);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn from_define_dir_reports_invalid_commands() {
+    let dir = scratch_dir("invalid");
+    for name in ["device.toml", "commands.toml", "error_codes.toml"] {
+        std::fs::write(dir.join(name), "this is [not valid toml").expect("write define file");
+    }
+
+    let err = match MockServer::from_define_dir(&dir) {
+        Ok(_) => panic!("expected invalid commands.toml error"),
+        Err(e) => e.to_string(),
+    };
+    assert!(err.contains("commands.toml"), "unexpected error: {}", err);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn explicit_registry_rejects_commands_it_does_not_define() {
+    if CommandRegistry::global().is_none() {
+        CommandRegistry::load_and_set_global_from_src().expect("load command registry");
+    }
+    let reg = CommandRegistry::global().expect("global registry");
+    let server = MockServer::new();
+    server.set_words("D", 0, &[0x1234]).await;
+
+    // a command the loaded set defines is served from the store
+    let mut read: Vec<u8> = Vec::new();
+    read.extend_from_slice(&0x0401u16.to_le_bytes());
+    read.extend_from_slice(&0x0000u16.to_le_bytes());
+    read.extend_from_slice(&[0x00, 0x00, 0x00, 0xA8]);
+    read.extend_from_slice(&1u16.to_le_bytes());
+    let req = McRequest::new()
+        .try_with_request_data(&read)
+        .expect("build read");
+    let resp = handler::handle_request_with_registry(&server.store, Some(reg), &req)
+        .await
+        .expect("defined command served");
+    assert_eq!(resp, vec![0x34, 0x12]);
+
+    // 0x9999 is not in the set: answered with 0xC059 instead of falling back
+    let mut unknown: Vec<u8> = Vec::new();
+    unknown.extend_from_slice(&0x9999u16.to_le_bytes());
+    unknown.extend_from_slice(&0x0000u16.to_le_bytes());
+    let req = McRequest::new()
+        .try_with_request_data(&unknown)
+        .expect("build unknown");
+    let err = handler::handle_request_with_registry(&server.store, Some(reg), &req)
+        .await
+        .expect_err("undefined command rejected");
+    assert_eq!(
+        err.downcast_ref::<EndCodeError>(),
+        Some(&EndCodeError(handler::END_CODE_COMMAND_NOT_SUPPORTED))
+    );
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use tokio::net::TcpStream;
+
+use melsec_mc_mock::handler::{self, EndCodeError};
+use melsec_mc_mock::MockServer;
+
+use common::{read_d_request, roundtrip, spawn_listener};
+
+#[tokio::test]
+async fn handler_rejects_reads_over_point_limit() {
+    let server = MockServer::new();
+    let too_many = u16::try_from(handler::MAX_WORD_POINTS + 1).unwrap();
+    let err = handler::handle_request_and_apply_store(&server.store, &read_d_request(0, too_many))
+        .await
+        .expect_err("expected point limit error");
+    assert_eq!(
+        err.downcast_ref::<EndCodeError>(),
+        Some(&EndCodeError(handler::END_CODE_POINTS_OUT_OF_RANGE))
+    );
+}
+
+#[tokio::test]
+async fn injected_and_limit_end_codes_are_returned_over_tcp() {
+    let server = MockServer::new();
+    server.set_words("D", 0, &[0x1234u16]).await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    // injected end-code answers exactly one request
+    server.inject_end_code(0xC056).await;
+    let (end_code, data) = roundtrip(&mut stream, &read_d_request(0, 1).build()).await;
+    assert_eq!(end_code, 0xC056);
+    // error information: access route (5) + command + subcommand
+    assert_eq!(data.len(), 9);
+    assert_eq!(&data[5..], &[0x01, 0x04, 0x00, 0x00]);
+
+    let (end_code, data) = roundtrip(&mut stream, &read_d_request(0, 1).build()).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0x34, 0x12]);
+
+    // exceeding the per-frame limit yields 0xC051
+    let too_many = u16::try_from(handler::MAX_WORD_POINTS + 1).unwrap();
+    let (end_code, _data) = roundtrip(&mut stream, &read_d_request(0, too_many).build()).await;
+    assert_eq!(end_code, handler::END_CODE_POINTS_OUT_OF_RANGE);
+}
+
+#[tokio::test]
+async fn injected_read_data_replaces_one_read_payload() {
+    let server = MockServer::new();
+    server.set_words("D", 0, &[0x1234u16]).await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    server.inject_read_data(vec![0x35, 0x12]).await;
+    let (end_code, data) = roundtrip(&mut stream, &read_d_request(0, 1).build()).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0x35, 0x12]);
+
+    // only one read is altered and the store itself is unchanged
+    let (_end_code, data) = roundtrip(&mut stream, &read_d_request(0, 1).build()).await;
+    assert_eq!(data, vec![0x34, 0x12]);
+    assert_eq!(server.get_words("D", 0, 1).await, vec![0x1234u16]);
+}
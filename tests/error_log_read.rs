@@ -0,0 +1,53 @@
+mod common;
+
+use melsec_mc_mock::device_map::ErrorLogEntry;
+use melsec_mc_mock::MockServer;
+use tokio::net::TcpStream;
+
+use common::{request, roundtrip, spawn_listener};
+
+fn error_log_request(start: u16, count: u16) -> Vec<u8> {
+    let mut req: Vec<u8> = Vec::new();
+    req.extend_from_slice(&0x0626u16.to_le_bytes());
+    req.extend_from_slice(&0x0000u16.to_le_bytes());
+    req.extend_from_slice(&start.to_le_bytes());
+    req.extend_from_slice(&count.to_le_bytes());
+    request(&req).build()
+}
+
+#[tokio::test]
+async fn error_log_read_returns_available_entries() {
+    let server = MockServer::new();
+    server
+        .push_error_log(ErrorLogEntry {
+            code: 0x1010,
+            timestamp: [0x26, 0x10, 0x15, 0x09, 0x30, 0x00],
+            detail: 0x0001,
+        })
+        .await;
+    server
+        .push_error_log(ErrorLogEntry {
+            code: 0x2220,
+            timestamp: [0x26, 0x10, 0x15, 0x09, 0x31, 0x45],
+            detail: 0x0000,
+        })
+        .await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    // ask for more entries than the CPU holds
+    let (end_code, data) = roundtrip(&mut stream, &error_log_request(0, 5)).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(
+        data,
+        vec![
+            0x02, 0x00, // returned count
+            0x10, 0x10, 0x26, 0x10, 0x15, 0x09, 0x30, 0x00, 0x01, 0x00, //
+            0x20, 0x22, 0x26, 0x10, 0x15, 0x09, 0x31, 0x45, 0x00, 0x00,
+        ]
+    );
+
+    let (end_code, data) = roundtrip(&mut stream, &error_log_request(2, 5)).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0x00, 0x00]);
+}
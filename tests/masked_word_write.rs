@@ -0,0 +1,54 @@
+mod common;
+
+use tokio::net::TcpStream;
+
+use melsec_mc_mock::handler;
+use melsec_mc_mock::MockServer;
+
+use common::{request, roundtrip, spawn_listener};
+
+fn masked_write_request(addr: u32, dev_code: u16, value: u16, mask: u16) -> Vec<u8> {
+    let mut req: Vec<u8> = Vec::new();
+    req.extend_from_slice(&handler::MASKED_WORD_WRITE_COMMAND.to_le_bytes());
+    req.extend_from_slice(&handler::MASKED_WORD_WRITE_SUBCOMMAND.to_le_bytes());
+    req.extend_from_slice(&addr.to_le_bytes());
+    req.extend_from_slice(&dev_code.to_le_bytes());
+    req.extend_from_slice(&value.to_le_bytes());
+    req.extend_from_slice(&mask.to_le_bytes());
+    request(&req).build()
+}
+
+#[tokio::test]
+async fn masked_write_changes_only_masked_bits() {
+    let server = MockServer::new().with_masked_word_write(true);
+    server
+        .set_words("0xA8", 100, &[0b1000_0000_0000_0001u16])
+        .await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    // set bit 5, clear bit 0 (both masked); bit 15 is outside the mask and
+    // must survive even though `value` has it cleared
+    let req = masked_write_request(100, 0x00A8, 0b0000_0000_0010_0000, 0b0000_0000_0010_0001);
+    let (end_code, data) = roundtrip(&mut stream, &req).await;
+    assert_eq!(end_code, 0x0000);
+    assert!(data.is_empty());
+
+    assert_eq!(
+        server.get_words("0xA8", 100, 1).await,
+        vec![0b1000_0000_0010_0000u16]
+    );
+}
+
+#[tokio::test]
+async fn masked_write_is_rejected_unless_enabled() {
+    let server = MockServer::new();
+    server.set_words("0xA8", 100, &[0x0001]).await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    let req = masked_write_request(100, 0x00A8, 0x0000, 0xFFFF);
+    let (end_code, _) = roundtrip(&mut stream, &req).await;
+    assert_eq!(end_code, handler::END_CODE_COMMAND_NOT_SUPPORTED);
+    assert_eq!(server.get_words("0xA8", 100, 1).await, vec![0x0001]);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use melsec_mc_mock::MockServer;
+
+use common::{roundtrip_exact, spawn_mc1e_listener};
+
+// 1E batch request: subheader, pc_no, monitoring timer, head device, device code, points, 0x00
+fn mc1e_request(sub: u8, head: u32, code: u16, points: u8, data: &[u8]) -> Vec<u8> {
+    let mut req: Vec<u8> = vec![sub, 0xFF];
+    req.extend_from_slice(&0x000Au16.to_le_bytes());
+    req.extend_from_slice(&head.to_le_bytes());
+    req.extend_from_slice(&code.to_le_bytes());
+    req.push(points);
+    req.push(0x00);
+    req.extend_from_slice(data);
+    req
+}
+
+#[tokio::test]
+async fn mc1e_word_and_bit_roundtrip_over_tcp() {
+    let server = MockServer::new();
+    let addr = spawn_mc1e_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    // word write D100..D101, then read back
+    let resp = roundtrip_exact(
+        &mut stream,
+        &mc1e_request(0x03, 100, 0x4420, 2, &[0x34, 0x12, 0x78, 0x56]),
+        2,
+    )
+    .await;
+    assert_eq!(resp, vec![0x83, 0x00]);
+    let resp = roundtrip_exact(&mut stream, &mc1e_request(0x01, 100, 0x4420, 2, &[]), 6).await;
+    assert_eq!(resp, vec![0x81, 0x00, 0x34, 0x12, 0x78, 0x56]);
+    // the 1E listener shares the store with the 3E/4E listeners
+    assert_eq!(server.get_words("D", 100, 2).await, vec![0x1234, 0x5678]);
+
+    // bit write M10..M12 = ON, OFF, ON, then read back
+    let resp = roundtrip_exact(
+        &mut stream,
+        &mc1e_request(0x02, 10, 0x4D20, 3, &[0x10, 0x10]),
+        2,
+    )
+    .await;
+    assert_eq!(resp, vec![0x82, 0x00]);
+    let resp = roundtrip_exact(&mut stream, &mc1e_request(0x00, 10, 0x4D20, 3, &[]), 4).await;
+    assert_eq!(resp, vec![0x80, 0x00, 0x10, 0x10]);
+}
+
+#[tokio::test]
+async fn mc1e_unknown_device_code_is_rejected() {
+    let server = MockServer::new();
+    let addr = spawn_mc1e_listener(server).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let resp = roundtrip_exact(&mut stream, &mc1e_request(0x01, 0, 0x3F3F, 1, &[]), 2).await;
+    assert_eq!(resp, vec![0x81, melsec_mc_mock::mc1e::END_CODE_DEVICE]);
+}
+
+#[tokio::test]
+async fn mc1e_listener_honours_refused_connects() {
+    let server = MockServer::new();
+    server.set_words("D", 0, &[0x00AA]).await;
+    server.refuse_next_connects(1).await;
+    let addr = spawn_mc1e_listener(server).await;
+
+    let mut refused = TcpStream::connect(addr).await.expect("connect");
+    let mut buf = [0u8; 1];
+    match timeout(Duration::from_secs(2), refused.read(&mut buf))
+        .await
+        .expect("reset arrives")
+    {
+        Ok(0) | Err(_) => {}
+        Ok(n) => panic!("refused connection delivered {} bytes", n),
+    }
+
+    let mut stream = TcpStream::connect(addr).await.expect("reconnect");
+    let resp = roundtrip_exact(&mut stream, &mc1e_request(0x01, 0, 0x4420, 1, &[]), 4).await;
+    assert_eq!(resp, vec![0x81, 0x00, 0xAA, 0x00]);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use melsec_mc_mock::handler;
+use melsec_mc_mock::MockServer;
+use tokio::net::TcpStream;
+
+use common::{request, roundtrip, spawn_listener};
+
+fn module_read_data(module_no: u16, start_byte: u32, byte_count: u16) -> Vec<u8> {
+    let mut req: Vec<u8> = Vec::new();
+    req.extend_from_slice(&0x0601u16.to_le_bytes());
+    req.extend_from_slice(&0x0000u16.to_le_bytes());
+    req.extend_from_slice(&start_byte.to_le_bytes());
+    req.extend_from_slice(&byte_count.to_le_bytes());
+    req.extend_from_slice(&module_no.to_le_bytes());
+    req
+}
+
+fn module_read_request(module_no: u16, start_byte: u32, byte_count: u16) -> Vec<u8> {
+    request(&module_read_data(module_no, start_byte, byte_count)).build()
+}
+
+#[tokio::test]
+async fn module_buffer_read_returns_seeded_words() {
+    let server = MockServer::new();
+    // module at start I/O 0x0040, buffer memory G16..G17
+    server
+        .set_module_words(0x0040, 16, &[0x1111u16, 0x2222u16])
+        .await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    // module number 0x0004, byte address 32 (= G16), 4 bytes
+    let (end_code, data) = roundtrip(&mut stream, &module_read_request(0x0004, 32, 4)).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0x11, 0x11, 0x22, 0x22]);
+
+    // other modules are unaffected and read as zero
+    let (end_code, data) = roundtrip(&mut stream, &module_read_request(0x0005, 32, 4)).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0u8; 4]);
+}
+
+#[tokio::test]
+async fn module_buffer_read_errors_reach_the_wire() {
+    let server = MockServer::new();
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    let too_many = u16::try_from(handler::MODULE_BUFFER_MAX_BYTES + 2).unwrap();
+    let (end_code, _) = roundtrip(&mut stream, &module_read_request(0x0004, 0, too_many)).await;
+    assert_eq!(end_code, handler::END_CODE_POINTS_OUT_OF_RANGE);
+
+    // odd start address
+    let (end_code, _) = roundtrip(&mut stream, &module_read_request(0x0004, 1, 4)).await;
+    assert_eq!(end_code, handler::END_CODE_POINTS_OUT_OF_RANGE);
+
+    // module number missing
+    let mut short = module_read_data(0x0004, 0, 4);
+    short.truncate(short.len() - 2);
+    let (end_code, _) = roundtrip(&mut stream, &request(&short).build()).await;
+    assert_eq!(end_code, handler::END_CODE_REQUEST_LENGTH);
+}
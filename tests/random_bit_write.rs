@@ -0,0 +1,92 @@
+mod common;
+
+use melsec_mc_mock::handler;
+use melsec_mc_mock::MockServer;
+use tokio::net::TcpStream;
+
+use common::{request, roundtrip, spawn_listener};
+
+// random bit write (0x1402/0x0001): point count, then device_no 3le, device_code 1, on/off 1
+fn random_bit_write_request(points: &[(u32, u8, bool)]) -> Vec<u8> {
+    let mut req: Vec<u8> = Vec::new();
+    req.extend_from_slice(&0x1402u16.to_le_bytes());
+    req.extend_from_slice(&0x0001u16.to_le_bytes());
+    req.push(u8::try_from(points.len()).unwrap());
+    for (addr, code, on) in points {
+        req.extend_from_slice(&addr.to_le_bytes()[..3]);
+        req.push(*code);
+        req.push(u8::from(*on));
+    }
+    request(&req).build()
+}
+
+// iQ-R layout (0x1402/0x0003): point count, then device_no 4le, device_code 2le, on/off 2le
+fn random_bit_write_request_iqr(points: &[(u32, u16, bool)]) -> Vec<u8> {
+    let mut req: Vec<u8> = Vec::new();
+    req.extend_from_slice(&0x1402u16.to_le_bytes());
+    req.extend_from_slice(&0x0003u16.to_le_bytes());
+    req.push(u8::try_from(points.len()).unwrap());
+    for (addr, code, on) in points {
+        req.extend_from_slice(&addr.to_le_bytes());
+        req.extend_from_slice(&code.to_le_bytes());
+        req.extend_from_slice(&u16::from(*on).to_le_bytes());
+    }
+    request(&req).build()
+}
+
+#[tokio::test]
+async fn random_bit_write_sets_and_resets_scattered_bits() {
+    let server = MockServer::new();
+    // M101 starts ON and is reset by the request
+    server.set_words("0x90", 101, &[1u16]).await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    // M5 ON, M100 ON, M101 OFF, Y20 (hex address 0x20) ON
+    let req = random_bit_write_request(&[
+        (5, 0x90, true),
+        (100, 0x90, true),
+        (101, 0x90, false),
+        (0x20, 0x9D, true),
+    ]);
+    let (end_code, data) = roundtrip(&mut stream, &req).await;
+    assert_eq!(end_code, 0x0000);
+    assert!(data.is_empty());
+
+    assert_eq!(server.get_words("0x90", 4, 3).await, vec![0, 1, 0]);
+    assert_eq!(server.get_words("0x90", 100, 2).await, vec![1, 0]);
+    assert_eq!(server.get_words("0x9D", 0x1F, 3).await, vec![0, 1, 0]);
+}
+
+#[tokio::test]
+async fn random_bit_write_iqr_layout() {
+    let server = MockServer::new();
+    server.set_words("0x90", 7, &[1u16]).await;
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    // M70000 ON (needs the 4-byte device number), M7 OFF
+    let req = random_bit_write_request_iqr(&[(70000, 0x0090, true), (7, 0x0090, false)]);
+    let (end_code, data) = roundtrip(&mut stream, &req).await;
+    assert_eq!(end_code, 0x0000);
+    assert!(data.is_empty());
+
+    assert_eq!(server.get_words("0x90", 69999, 3).await, vec![0, 1, 0]);
+    assert_eq!(server.get_words("0x90", 7, 1).await, vec![0]);
+}
+
+#[tokio::test]
+async fn random_bit_write_rejects_missing_or_zero_point_count() {
+    let server = MockServer::new();
+    let addr = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    let (end_code, _) = roundtrip(&mut stream, &random_bit_write_request(&[])).await;
+    assert_eq!(end_code, handler::END_CODE_POINTS_OUT_OF_RANGE);
+
+    let mut no_count: Vec<u8> = Vec::new();
+    no_count.extend_from_slice(&0x1402u16.to_le_bytes());
+    no_count.extend_from_slice(&0x0001u16.to_le_bytes());
+    let (end_code, _) = roundtrip(&mut stream, &request(&no_count).build()).await;
+    assert_eq!(end_code, handler::END_CODE_REQUEST_LENGTH);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use tokio::net::TcpStream;
+
+use melsec_mc_mock::device_map::ReadDefault;
+use melsec_mc_mock::MockServer;
+
+use common::{read_d_request, roundtrip, spawn_listener};
+
+/// Read D100..D102 over TCP after writing only D101.
+async fn read_around_written_word(server: MockServer) -> Vec<u8> {
+    server.set_words("D", 101, &[0x1234u16]).await;
+    let addr = spawn_listener(server).await;
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let (end_code, data) = roundtrip(&mut stream, &read_d_request(100, 3).build()).await;
+    assert_eq!(end_code, 0x0000);
+    data
+}
+
+#[tokio::test]
+async fn read_default_modes_apply_on_the_wire() {
+    let zero = read_around_written_word(MockServer::new()).await;
+    assert_eq!(zero, vec![0x00, 0x00, 0x34, 0x12, 0x00, 0x00]);
+
+    let fill = read_around_written_word(
+        MockServer::new()
+            .with_read_default(ReadDefault::Fill(0xAAAA))
+            .await,
+    )
+    .await;
+    assert_eq!(fill, vec![0xAA, 0xAA, 0x34, 0x12, 0xAA, 0xAA]);
+
+    let echo = read_around_written_word(
+        MockServer::new()
+            .with_read_default(ReadDefault::AddressEcho)
+            .await,
+    )
+    .await;
+    assert_eq!(echo, vec![100, 0x00, 0x34, 0x12, 102, 0x00]);
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use melsec_mc_mock::MockServer;
+
+use common::{read_d_request, roundtrip, spawn_listener};
+
+#[tokio::test]
+async fn refused_connect_is_reset_then_next_connect_is_served() {
+    let server = MockServer::new();
+    server.set_words("D", 0, &[0x00AA]).await;
+    server.refuse_next_connects(1).await;
+    let addr = spawn_listener(server.clone()).await;
+
+    // first connection is reset before any request is answered
+    let mut refused = TcpStream::connect(addr).await.expect("connect");
+    let mut buf = [0u8; 1];
+    match timeout(Duration::from_secs(2), refused.read(&mut buf))
+        .await
+        .expect("reset arrives")
+    {
+        Ok(0) | Err(_) => {}
+        Ok(n) => panic!("refused connection delivered {} bytes", n),
+    }
+
+    // the retry is accepted and served normally
+    let mut stream = TcpStream::connect(addr).await.expect("reconnect");
+    let (end_code, data) = roundtrip(&mut stream, &read_d_request(0, 1).build()).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0xAA, 0x00]);
+}
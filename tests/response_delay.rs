@@ -0,0 +1,34 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use melsec_mc_mock::MockServer;
+
+use common::{read_d_request, read_response_frame, spawn_listener};
+
+#[tokio::test]
+async fn slow_mock_holds_response_for_configured_delay() {
+    let server = MockServer::new();
+    server.set_response_delay(Duration::from_millis(300)).await;
+    let addr = spawn_listener(server.clone()).await;
+
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let started = Instant::now();
+    stream
+        .write_all(&read_d_request(0, 1).build())
+        .await
+        .expect("write");
+
+    // a caller giving up early sees nothing yet
+    assert!(timeout(Duration::from_millis(100), stream.readable())
+        .await
+        .is_err());
+
+    let frame = read_response_frame(&mut stream).await;
+    assert!(!frame.is_empty());
+    assert!(started.elapsed() >= Duration::from_millis(300));
+}
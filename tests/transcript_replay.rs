@@ -0,0 +1,60 @@
+mod common;
+
+use tokio::net::TcpStream;
+
+use melsec_mc_mock::MockServer;
+
+use common::{exchange, hex, mc4e_read_d, spawn_listener};
+
+async fn start(server: MockServer) -> TcpStream {
+    let addr = spawn_listener(server).await;
+    TcpStream::connect(addr).await.expect("connect")
+}
+
+#[tokio::test]
+async fn captured_session_replays_against_empty_mock() {
+    // capture: a live mock stands in for the field PLC
+    let live = MockServer::new();
+    live.set_words("D", 200, &[0x1111u16, 0x2222u16]).await;
+    let mut stream = start(live).await;
+    let req = mc4e_read_d(0x0001, 200, 2);
+    let resp = exchange(&mut stream, &req).await;
+
+    let path = std::env::temp_dir().join(format!(
+        "melsec_mock_transcript_{}.jsonl",
+        std::process::id()
+    ));
+    let line = serde_json::json!({
+        "ts": "2026-10-15T09:30:00.000Z",
+        "op": "read_words",
+        "request": hex(&req),
+        "response": hex(&resp),
+        "result": [0x1111, 0x2222],
+    });
+    std::fs::write(&path, format!("{}\n", line)).expect("write transcript");
+
+    // replay: the store is empty, so data can only come from the transcript
+    let replay = MockServer::from_transcript(&path).expect("load transcript");
+    let mut stream = start(replay).await;
+    let replayed = exchange(&mut stream, &mc4e_read_d(0x0042, 200, 2)).await;
+    // the serial follows the new request, the rest matches the capture
+    assert_eq!(&replayed[2..4], &0x0042u16.to_le_bytes());
+    assert_eq!(&replayed[4..], &resp[4..]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn from_transcript_reports_malformed_lines() {
+    let path = std::env::temp_dir().join(format!(
+        "melsec_mock_transcript_bad_{}.jsonl",
+        std::process::id()
+    ));
+    std::fs::write(&path, "{\"request\":\"54 00\",\"response\":\"XYZ\"}\n").expect("write");
+    let err = match MockServer::from_transcript(&path) {
+        Ok(_) => panic!("expected malformed transcript error"),
+        Err(e) => format!("{:#}", e),
+    };
+    assert!(err.contains(":1"), "unexpected error: {}", err);
+    let _ = std::fs::remove_file(&path);
+}
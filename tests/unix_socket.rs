@@ -0,0 +1,123 @@
+#![cfg(all(unix, feature = "unix-socket"))]
+
+mod common;
+
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::net::UnixStream;
+use tokio::time::timeout;
+
+use melsec_mc_mock::MockServer;
+
+use melsec_mc::mc_define::{AccessRoute, MC_SUBHEADER_REQUEST, MC_SUBHEADER_RESPONSE};
+
+use common::{end_code_and_data, exchange, read_d_request, roundtrip};
+
+fn socket_path(label: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("melsec_mock_{}_{}.sock", label, std::process::id()))
+}
+
+async fn connect_when_ready(path: &Path) -> UnixStream {
+    timeout(Duration::from_secs(2), async {
+        loop {
+            if let Ok(s) = UnixStream::connect(path).await {
+                return s;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("connect timeout")
+}
+
+#[tokio::test]
+async fn read_words_over_unix_socket() {
+    let path = socket_path("read");
+    let server = MockServer::new();
+    server.set_words("D", 10, &[0xBEEFu16]).await;
+    let srv = server.clone();
+    let bind_path = path.clone();
+    tokio::spawn(async move {
+        let _ = srv.run_unix_listener(&bind_path).await;
+    });
+
+    let mut stream = connect_when_ready(&path).await;
+    let (end_code, data) = roundtrip(&mut stream, &read_d_request(10, 1).build()).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0xEF, 0xBE]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn malformed_frame_over_unix_socket_gets_error_response() {
+    let path = socket_path("malformed");
+    let srv = MockServer::new();
+    let bind_path = path.clone();
+    tokio::spawn(async move {
+        let _ = srv.run_unix_listener(&bind_path).await;
+    });
+
+    // MC4E header with data_len = 1 (must be >= 2): detect_frame rejects it
+    let mut frame: Vec<u8> = Vec::new();
+    frame.extend_from_slice(&MC_SUBHEADER_REQUEST);
+    frame.extend_from_slice(&0x1234u16.to_le_bytes());
+    frame.extend_from_slice(&0u16.to_le_bytes());
+    frame.extend_from_slice(&AccessRoute::default().to_bytes());
+    frame.extend_from_slice(&1u16.to_le_bytes());
+    frame.extend_from_slice(&[0u8, 0u8]);
+
+    let mut stream = connect_when_ready(&path).await;
+    let resp = exchange(&mut stream, &frame).await;
+    assert_eq!(resp[..2], MC_SUBHEADER_RESPONSE);
+    assert_eq!(resp[2..4], 0x1234u16.to_le_bytes());
+    assert_eq!(end_code_and_data(&resp), (0x0050, vec![]));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn unix_listener_refuses_to_replace_regular_file() {
+    let path = socket_path("regular");
+    std::fs::write(&path, b"keep me").expect("write regular file");
+
+    let err = MockServer::new()
+        .run_unix_listener(&path)
+        .await
+        .expect_err("regular file must not be replaced");
+    assert!(
+        err.to_string().contains("not a Unix socket"),
+        "unexpected error: {}",
+        err
+    );
+    assert_eq!(std::fs::read(&path).expect("file kept"), b"keep me");
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn unix_listener_refuses_to_replace_live_socket() {
+    let path = socket_path("live");
+    let srv = MockServer::new();
+    let bind_path = path.clone();
+    tokio::spawn(async move {
+        let _ = srv.run_unix_listener(&bind_path).await;
+    });
+    let mut stream = connect_when_ready(&path).await;
+
+    let err = MockServer::new()
+        .run_unix_listener(&path)
+        .await
+        .expect_err("live socket must not be replaced");
+    assert!(
+        err.to_string().contains("in use"),
+        "unexpected error: {}",
+        err
+    );
+    // the first listener keeps serving
+    let (end_code, _) = roundtrip(&mut stream, &read_d_request(0, 1).build()).await;
+    assert_eq!(end_code, 0x0000);
+
+    let _ = std::fs::remove_file(&path);
+}
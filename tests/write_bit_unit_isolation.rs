@@ -0,0 +1,36 @@
+use melsec_mc::request::McRequest;
+use melsec_mc_mock::handler;
+use melsec_mc_mock::MockServer;
+
+/// Build a bit-unit write (command 0x1401, sub 0x0001) for a single M point.
+fn write_single_m_bit(addr: u32, on: bool) -> McRequest {
+    let mut req: Vec<u8> = Vec::new();
+    req.extend_from_slice(&0x1401u16.to_le_bytes());
+    req.extend_from_slice(&0x0001u16.to_le_bytes());
+    req.extend_from_slice(&addr.to_le_bytes()[..3]); // start addr 3le
+    req.push(0x90u8); // device code M
+    req.extend_from_slice(&1u16.to_le_bytes()); // count = 1 bit
+    req.push(if on { 0x10u8 } else { 0x00u8 }); // high nibble carries the point
+    let built = McRequest::new()
+        .try_with_request_data(&req)
+        .expect("build bit write");
+    McRequest::try_from_payload(&built.build()).expect("parse bit write")
+}
+
+#[tokio::test]
+async fn bit_unit_write_m5_leaves_neighbours_untouched() {
+    let server = MockServer::new();
+    // M4 and M6 on, M5 off
+    server.set_words("M", 4, &[1u16, 0u16, 1u16]).await;
+
+    let resp = handler::handle_request_and_apply_store(&server.store, &write_single_m_bit(5, true))
+        .await
+        .expect("handler ok");
+    assert!(resp.is_empty(), "write_bits returns empty payload");
+    assert_eq!(server.get_words("0x90", 4, 3).await, vec![1u16, 1u16, 1u16]);
+
+    let _ = handler::handle_request_and_apply_store(&server.store, &write_single_m_bit(5, false))
+        .await
+        .expect("handler ok");
+    assert_eq!(server.get_words("0x90", 4, 3).await, vec![1u16, 0u16, 1u16]);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use melsec_mc_mock::MockServer;
+use tokio::net::TcpStream;
+
+use common::{request, roundtrip, spawn_listener};
+
+// R-series layout: start_addr 4le, device_code 2le, count 2le
+fn zr_request(command: u16, sub: u16, addr: u32, count: u16, words: &[u16]) -> Vec<u8> {
+    let mut req: Vec<u8> = Vec::new();
+    req.extend_from_slice(&command.to_le_bytes());
+    req.extend_from_slice(&sub.to_le_bytes());
+    req.extend_from_slice(&addr.to_le_bytes());
+    req.extend_from_slice(&0x00B0u16.to_le_bytes()); // ZR
+    req.extend_from_slice(&count.to_le_bytes());
+    for w in words {
+        req.extend_from_slice(&w.to_le_bytes());
+    }
+    request(&req).build()
+}
+
+#[tokio::test]
+async fn zr_write_then_read_beyond_three_byte_range() {
+    let server = MockServer::new();
+    let listen = spawn_listener(server.clone()).await;
+    let mut stream = TcpStream::connect(listen).await.expect("connect");
+    let addr = 0x0123_4567u32;
+
+    let wreq = zr_request(0x1401, 0x0002, addr, 2, &[0xCAFEu16, 0xBEEFu16]);
+    let (end_code, data) = roundtrip(&mut stream, &wreq).await;
+    assert_eq!(end_code, 0x0000);
+    assert!(data.is_empty());
+
+    let rreq = zr_request(0x0401, 0x0002, addr, 2, &[]);
+    let (end_code, data) = roundtrip(&mut stream, &rreq).await;
+    assert_eq!(end_code, 0x0000);
+    assert_eq!(data, vec![0xFE, 0xCA, 0xEF, 0xBE]);
+
+    assert_eq!(
+        server.get_words("0xB0", addr as usize, 2).await,
+        vec![0xCAFEu16, 0xBEEFu16]
+    );
+}